@@ -0,0 +1,95 @@
+//! Adjusting the number of concurrent requests to meet a response time objective
+
+use std::num::NonZeroUsize;
+
+use crate::config::LatencySlo;
+use crate::search::find_percentile;
+use crate::stats::{Percentile, Sample};
+
+/// Fraction of the limit kept after a sample missed the objective
+const DECREASE_FACTOR: f64 = 0.75;
+
+/// Chooses the limit of concurrent requests per thread, based on the response times of
+/// the recent sample, using additive increase / multiplicative decrease.
+/// Starts from a single request and doubles the limit until the objective is missed for
+/// the first time. Then increases the limit by one after each sample meeting the objective
+/// and decreases it by `DECREASE_FACTOR` after each sample missing it.
+pub struct ConcurrencyController {
+    percentile: Percentile,
+    max_resp_time_ms: f64,
+    max_limit: usize,
+    limit: usize,
+    slow_start: bool,
+}
+
+impl ConcurrencyController {
+    /// Creates a controller keeping the limit at or below `max_limit`.
+    /// The percentile of the objective must be one of the reported percentiles.
+    pub fn new(slo: &LatencySlo, max_limit: NonZeroUsize) -> ConcurrencyController {
+        ConcurrencyController {
+            percentile: find_percentile(slo).expect("Unsupported objective percentile"),
+            max_resp_time_ms: slo.max_resp_time.as_secs_f64() * 1000.0,
+            max_limit: max_limit.get(),
+            limit: 1,
+            slow_start: true,
+        }
+    }
+
+    /// Returns the current limit of concurrent requests per thread
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Adjusts the limit based on the response times observed in the sample.
+    /// Returns the new limit.
+    pub fn update(&mut self, sample: &Sample) -> usize {
+        if sample.request_count == 0 {
+            return self.limit;
+        }
+        let resp_time_ms = sample.resp_time_percentiles[self.percentile as usize] as f64;
+        self.adjust(resp_time_ms)
+    }
+
+    fn adjust(&mut self, resp_time_ms: f64) -> usize {
+        if resp_time_ms <= self.max_resp_time_ms {
+            let limit = if self.slow_start {
+                2 * self.limit
+            } else {
+                self.limit + 1
+            };
+            self.limit = limit.min(self.max_limit);
+        } else {
+            self.slow_start = false;
+            self.limit = ((self.limit as f64 * DECREASE_FACTOR) as usize).max(1);
+        }
+        self.limit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroUsize;
+
+    use crate::concurrency::ConcurrencyController;
+
+    #[test]
+    fn increases_and_decreases_limit() {
+        let slo = "p99<10ms".parse().unwrap();
+        let mut controller = ConcurrencyController::new(&slo, NonZeroUsize::new(100).unwrap());
+        let limits: Vec<_> = [1.0, 1.0, 1.0, 1.0, 20.0, 1.0, 1.0, 20.0]
+            .into_iter()
+            .map(|resp_time_ms| controller.adjust(resp_time_ms))
+            .collect();
+        assert_eq!(limits, vec![2, 4, 8, 16, 12, 13, 14, 10]);
+    }
+
+    #[test]
+    fn respects_max_limit() {
+        let slo = "p99<10ms".parse().unwrap();
+        let mut controller = ConcurrencyController::new(&slo, NonZeroUsize::new(5).unwrap());
+        for _ in 0..10 {
+            controller.adjust(1.0);
+        }
+        assert_eq!(controller.limit(), 5);
+    }
+}
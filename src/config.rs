@@ -1,13 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use chrono::Utc;
 use clap::builder::PossibleValue;
 use clap::{Parser, ValueEnum};
+use itertools::Itertools;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 /// Parse a single key-value pair
@@ -27,7 +32,7 @@ where
 /// Controls how long the benchmark should run.
 /// We can specify either a time-based duration or a number of calls to perform.
 /// It is also used for controlling sampling.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Interval {
     Count(u64),
     Time(tokio::time::Duration),
@@ -81,6 +86,16 @@ impl FromStr for Interval {
     }
 }
 
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interval::Count(c) => write!(f, "{c}"),
+            Interval::Time(d) => write!(f, "{d:?}"),
+            Interval::Unbounded => write!(f, "unbounded"),
+        }
+    }
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize)]
 pub struct ConnectionConf {
     /// Number of connections per Cassandra node / Scylla shard.
@@ -120,9 +135,824 @@ pub struct ConnectionConf {
     #[clap(long("ssl-key"), value_name = "PATH")]
     pub ssl_key_file: Option<PathBuf>,
 
+    /// Compression of the frames sent between the client and the server
+    #[clap(long("compression"), value_name = "ALGORITHM")]
+    pub compression: Option<Compression>,
+
     /// Default CQL query consistency level
     #[clap(long("consistency"), required = false, default_value = "LOCAL_QUORUM")]
     pub consistency: Consistency,
+
+    /// Local datacenter.
+    /// Requests are sent to the nodes of the local datacenter first.
+    #[clap(long("datacenter"), value_name = "NAME")]
+    pub datacenter: Option<String>,
+
+    /// Local rack in the local datacenter.
+    /// Requests are sent to the nodes in the local rack first.
+    #[clap(long("rack"), value_name = "NAME", requires = "datacenter")]
+    pub rack: Option<String>,
+
+    /// Disable routing requests directly to the replicas owning the data
+    #[clap(long("no-token-aware"))]
+    #[serde(default)]
+    pub no_token_aware: bool,
+
+    /// Disable connecting to the shard-aware port of ScyllaDB nodes
+    #[clap(long("no-shard-aware"))]
+    #[serde(default)]
+    pub no_shard_aware: bool,
+
+    /// Named execution profile that can be selected by the workload script.
+    /// Format: NAME:KEY=VALUE,... Supported keys are: consistency, serial_consistency, timeout,
+    /// retry (default | fallthrough | downgrading), datacenter,
+    /// speculative (delay, e.g. 10ms, or percentile, e.g. p99) and speculative_retries.
+    /// Options not given are taken from the connection settings.
+    /// Can be used multiple times.
+    #[clap(
+        long("profile"),
+        value_name = "NAME:KEY=VALUE,...",
+        number_of_values = 1
+    )]
+    #[serde(default)]
+    pub profiles: Vec<ExecutionProfileConf>,
+
+    /// Sends an additional speculative request to another replica if the response doesn't
+    /// arrive within given time (e.g. 10ms) or takes longer than given percentile
+    /// of response times of the node (e.g. p99). Applies only to idempotent statements.
+    #[clap(long("speculative"), value_name = "TIME | PERCENTILE")]
+    pub speculative: Option<SpeculativeExecution>,
+
+    /// Maximum number of speculative requests sent in addition to the original request
+    #[clap(long("speculative-retries"), default_value = "1", value_name = "COUNT")]
+    #[serde(default)]
+    pub speculative_retries: usize,
+
+    // Retrying of failed requests.
+    #[clap(flatten)]
+    #[serde(default)]
+    pub retry: RetryConf,
+}
+
+/// Controls how the delays between retries of a failed request are computed.
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum RetryPolicy {
+    /// Failed requests are not retried
+    #[default]
+    None,
+    /// Retries are separated by a constant delay
+    Fixed,
+    /// The delay doubles with each retry
+    Exponential,
+}
+
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct RetryConf {
+    /// Policy of retrying requests failed because of timeouts, overload or unavailable replicas.
+    /// Each request is recorded once, with the response time covering all attempts.
+    #[clap(long("retry-policy"), default_value = "none", value_name = "POLICY")]
+    pub policy: RetryPolicy,
+
+    /// Maximum number of retries of a failed request
+    #[clap(long("retries"), default_value = "3", value_name = "COUNT")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry
+    #[clap(long("retry-delay"), default_value = "100ms", value_name = "TIME", value_parser = parse_duration::parse)]
+    pub delay: Duration,
+
+    /// Maximum delay between retries with the exponential retry policy
+    #[clap(long("retry-max-delay"), default_value = "5s", value_name = "TIME", value_parser = parse_duration::parse)]
+    pub max_delay: Duration,
+
+    /// Retry also the statements not marked as idempotent.
+    /// By default, only idempotent statements are retried.
+    #[clap(long("retry-non-idempotent"))]
+    pub retry_non_idempotent: bool,
+}
+
+impl RetryConf {
+    /// Returns the delay before the next attempt,
+    /// given the number of attempts that have already failed.
+    /// Returns `None` if the request should not be retried anymore.
+    pub fn delay(&self, failed_attempts: u32) -> Option<Duration> {
+        if failed_attempts == 0 || failed_attempts > self.max_retries {
+            return None;
+        }
+        match self.policy {
+            RetryPolicy::None => None,
+            RetryPolicy::Fixed => Some(self.delay),
+            RetryPolicy::Exponential => {
+                let multiplier = 2u32.saturating_pow(failed_attempts - 1);
+                let delay = self.delay.saturating_mul(multiplier);
+                Some(delay.min(self.max_delay.max(self.delay)))
+            }
+        }
+    }
+}
+
+impl Default for RetryConf {
+    fn default() -> Self {
+        RetryConf {
+            policy: RetryPolicy::None,
+            max_retries: 3,
+            delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl Display for RetryConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.policy {
+            RetryPolicy::None => write!(f, "none"),
+            RetryPolicy::Fixed => write!(f, "fixed, {} × {:?}", self.max_retries, self.delay),
+            RetryPolicy::Exponential => write!(
+                f,
+                "exponential, {} × {:?}..{:?}",
+                self.max_retries, self.delay, self.max_delay
+            ),
+        }
+    }
+}
+
+/// Parses a number in range [0, 1]
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Ok(f),
+        Ok(_) => Err(format!("Fraction {s} out of range [0, 1]")),
+        Err(e) => Err(format!("Invalid fraction {s}: {e}")),
+    }
+}
+
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct TracingConf {
+    /// Fraction of requests to execute with CQL tracing enabled, e.g. 0.001.
+    /// Traces of the slowest traced requests are fetched after the run and saved in the report.
+    /// Failed requests are not traced, because the driver doesn't return their tracing ids.
+    #[clap(long("trace-sample"), value_name = "FRACTION", value_parser = parse_fraction)]
+    pub sample: Option<f64>,
+
+    /// Keep only the traces of requests slower than given time.
+    /// Without --trace-sample, enables tracing of all requests, which adds significant load.
+    #[clap(long("trace-threshold"), value_name = "TIME", value_parser = parse_duration::parse)]
+    pub threshold: Option<Duration>,
+
+    /// Maximum number of traces of the slowest requests saved in the report
+    #[clap(
+        id = "trace_count",
+        long("trace-count"),
+        default_value = "10",
+        value_name = "COUNT"
+    )]
+    pub count: usize,
+}
+
+impl TracingConf {
+    pub fn is_enabled(&self) -> bool {
+        (self.sample.is_some() || self.threshold.is_some()) && self.count > 0
+    }
+
+    /// Returns the fraction of requests that should be traced
+    pub fn sample_ratio(&self) -> f64 {
+        match (self.sample, self.threshold) {
+            (Some(sample), _) => sample,
+            (None, Some(_)) => 1.0,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Randomly decides if the next request should be traced
+    pub fn sample_next(&self) -> bool {
+        self.is_enabled() && rand::thread_rng().gen_bool(self.sample_ratio())
+    }
+}
+
+impl Default for TracingConf {
+    fn default() -> Self {
+        TracingConf {
+            sample: None,
+            threshold: None,
+            count: 10,
+        }
+    }
+}
+
+impl Display for TracingConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.is_enabled() {
+            return write!(f, "none");
+        }
+        write!(f, "{}", self.sample_ratio())?;
+        if let Some(threshold) = self.threshold {
+            write!(f, ", > {threshold:?}")?;
+        }
+        write!(f, ", top {}", self.count)
+    }
+}
+
+/// Name of a workload function together with the relative frequency of its calls
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightedFunction {
+    pub name: String,
+    pub weight: f64,
+}
+
+impl FromStr for WeightedFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, weight) = match s.split_once(':') {
+            Some((name, weight)) => {
+                let weight: f64 = weight
+                    .parse()
+                    .map_err(|e| format!("Invalid weight of function {name}: {e}"))?;
+                (name, weight)
+            }
+            None => (s, 1.0),
+        };
+        if name.is_empty() {
+            return Err("Function name must not be empty".to_string());
+        }
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(format!("Weight of function {name} must be positive"));
+        }
+        Ok(WeightedFunction {
+            name: name.to_string(),
+            weight,
+        })
+    }
+}
+
+impl Display for WeightedFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.weight)
+    }
+}
+
+/// Reads the list of functions from the report.
+/// Older reports store a single function name.
+fn deserialize_functions<'de, D>(deserializer: D) -> Result<Vec<WeightedFunction>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Functions {
+        Single(String),
+        Weighted(Vec<WeightedFunction>),
+    }
+    Ok(match Functions::deserialize(deserializer)? {
+        Functions::Single(name) => vec![WeightedFunction { name, weight: 1.0 }],
+        Functions::Weighted(functions) => functions,
+    })
+}
+
+/// Describes how the rate of cycles changes over the time of the run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RateProfile {
+    /// Changes the rate linearly from the first to the second value over the duration of the run
+    Ramp { from: f64, to: f64 },
+    /// Divides the duration of the run into equal steps, each executed at the next rate
+    Steps(Vec<f64>),
+    /// Oscillates the rate around the mean with given amplitude and period
+    Sine {
+        mean: f64,
+        amplitude: f64,
+        period: Duration,
+    },
+    /// Interpolates linearly between (time, rate) points loaded from a CSV file.
+    /// The time is given in seconds since the start of the run.
+    Points {
+        path: PathBuf,
+        points: Vec<(f64, f64)>,
+    },
+}
+
+impl RateProfile {
+    /// Returns true if the rate depends on the total duration of the run.
+    pub fn needs_duration(&self) -> bool {
+        matches!(self, RateProfile::Ramp { .. } | RateProfile::Steps(_))
+    }
+
+    /// Returns the time since the start of the run after which the rate doesn't change anymore,
+    /// or `None` if the rate keeps changing until the end of the run.
+    pub fn constant_after(&self, duration: Option<Duration>) -> Option<Duration> {
+        match self {
+            RateProfile::Ramp { .. } | RateProfile::Steps(_) => duration,
+            RateProfile::Sine { amplitude, .. } if *amplitude == 0.0 => Some(Duration::ZERO),
+            RateProfile::Sine { .. } => None,
+            RateProfile::Points { points, .. } => {
+                Some(Duration::from_secs_f64(points.last().unwrap().0.max(0.0)))
+            }
+        }
+    }
+
+    /// Returns the target rate at time `t` since the start of the run.
+    /// The `duration` is the total duration of the run; it is required by ramps and steps.
+    pub fn rate_at(&self, t: Duration, duration: Option<Duration>) -> f64 {
+        let progress = || {
+            duration
+                .map(|d| (t.as_secs_f64() / d.as_secs_f64()).clamp(0.0, 1.0))
+                .unwrap_or(0.0)
+        };
+        let rate = match self {
+            RateProfile::Ramp { from, to } => from + (to - from) * progress(),
+            RateProfile::Steps(rates) => {
+                let index = (progress() * rates.len() as f64) as usize;
+                rates[index.min(rates.len() - 1)]
+            }
+            RateProfile::Sine {
+                mean,
+                amplitude,
+                period,
+            } => {
+                let phase = 2.0 * std::f64::consts::PI * t.as_secs_f64() / period.as_secs_f64();
+                mean + amplitude * phase.sin()
+            }
+            RateProfile::Points { points, .. } => {
+                let t = t.as_secs_f64();
+                match points.iter().position(|(time, _)| *time > t) {
+                    Some(0) => points[0].1,
+                    Some(i) => {
+                        let (t0, r0) = points[i - 1];
+                        let (t1, r1) = points[i];
+                        r0 + (r1 - r0) * (t - t0) / (t1 - t0)
+                    }
+                    None => points.last().unwrap().1,
+                }
+            }
+        };
+        rate.max(0.0)
+    }
+
+    /// Reads (time, rate) points from a CSV file.
+    /// Empty lines, lines starting with `#` and a header line are skipped.
+    fn load_points(path: &Path) -> Result<Vec<(f64, f64)>, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read rate profile {}: {e}", path.display()))?;
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line
+                .split_once(',')
+                .and_then(|(t, r)| Some((t.trim().parse().ok()?, r.trim().parse().ok()?)));
+            let (time, rate): (f64, f64) = match parsed {
+                Some(point) => point,
+                None if points.is_empty() && i == 0 => continue,
+                None => return Err(format!("Invalid rate profile point at line {}", i + 1)),
+            };
+            if rate < 0.0 || points.last().map(|(t, _)| time <= *t).unwrap_or(time < 0.0) {
+                return Err(format!(
+                    "Invalid rate profile point at line {}: times must be increasing \
+                     and rates must not be negative",
+                    i + 1
+                ));
+            }
+            points.push((time, rate));
+        }
+        if points.is_empty() {
+            return Err(format!("Rate profile {} has no points", path.display()));
+        }
+        Ok(points)
+    }
+}
+
+/// Parses rate profiles given as `ramp:FROM,TO`, `steps:RATE,RATE,...`,
+/// `sine:MEAN,AMPLITUDE,PERIOD` or `csv:PATH`.
+impl FromStr for RateProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid rate profile {s}: no `:` found"))?;
+        let rates = |args: &str| -> Result<Vec<f64>, String> {
+            args.split(',')
+                .map(|r| match r.trim().parse::<f64>() {
+                    Ok(r) if r >= 0.0 => Ok(r),
+                    _ => Err(format!("Invalid rate {r}")),
+                })
+                .collect()
+        };
+        match kind {
+            "ramp" => match rates(args)?.as_slice() {
+                [from, to] => Ok(RateProfile::Ramp {
+                    from: *from,
+                    to: *to,
+                }),
+                _ => Err("Ramp profile requires two rates: FROM,TO".to_string()),
+            },
+            "steps" => Ok(RateProfile::Steps(rates(args)?)),
+            "sine" => match args.split(',').collect_vec().as_slice() {
+                [mean, amplitude, period] => {
+                    let rates = rates(&format!("{mean},{amplitude}"))?;
+                    let period = parse_duration::parse(period).map_err(|e| e.to_string())?;
+                    if period.is_zero() {
+                        return Err("Sine profile period must be positive".to_string());
+                    }
+                    Ok(RateProfile::Sine {
+                        mean: rates[0],
+                        amplitude: rates[1],
+                        period,
+                    })
+                }
+                _ => Err("Sine profile requires MEAN,AMPLITUDE,PERIOD".to_string()),
+            },
+            "csv" => {
+                let path = PathBuf::from(args);
+                let points = Self::load_points(&path)?;
+                Ok(RateProfile::Points { path, points })
+            }
+            _ => Err(format!("Unknown rate profile {kind}")),
+        }
+    }
+}
+
+impl Display for RateProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateProfile::Ramp { from, to } => write!(f, "ramp:{from},{to}"),
+            RateProfile::Steps(rates) => write!(f, "steps:{}", rates.iter().join(",")),
+            RateProfile::Sine {
+                mean,
+                amplitude,
+                period,
+            } => write!(f, "sine:{mean},{amplitude},{period:?}"),
+            RateProfile::Points { path, .. } => write!(f, "csv:{}", path.display()),
+        }
+    }
+}
+
+/// Controls the distribution of the intervals between the scheduled starts of the cycles
+/// when the rate is limited.
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum Arrivals {
+    /// Cycles are spaced evenly
+    #[default]
+    Fixed,
+    /// Intervals are exponentially distributed, so the cycles arrive as a Poisson process
+    Poisson,
+    /// Intervals are distributed uniformly between zero and twice the mean interval
+    Uniform,
+}
+
+impl Arrivals {
+    /// Returns a random interval to the next arrival, as a multiple of the mean interval.
+    pub fn next_interval(&self, rng: &mut impl Rng) -> f64 {
+        match self {
+            Arrivals::Fixed => 1.0,
+            Arrivals::Poisson => -(1.0 - rng.gen::<f64>()).ln(),
+            Arrivals::Uniform => rng.gen_range(0.0..2.0),
+        }
+    }
+}
+
+impl Display for Arrivals {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arrivals::Fixed => write!(f, "fixed"),
+            Arrivals::Poisson => write!(f, "poisson"),
+            Arrivals::Uniform => write!(f, "uniform"),
+        }
+    }
+}
+
+/// Response time objective given as `pPERCENTILE<TIME`, e.g. `p99<10ms`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LatencySlo {
+    pub percentile: f64,
+    pub max_resp_time: Duration,
+}
+
+impl FromStr for LatencySlo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (percentile, max_resp_time) = s.split_once('<').ok_or_else(|| {
+            format!("Invalid objective {s}: expected PERCENTILE<TIME, e.g. p99<10ms")
+        })?;
+        let percentile = percentile
+            .trim()
+            .strip_prefix('p')
+            .and_then(|p| p.parse::<f64>().ok())
+            .filter(|p| (0.0..=100.0).contains(p))
+            .ok_or_else(|| format!("Invalid percentile {percentile}"))?;
+        let max_resp_time =
+            parse_duration::parse(max_resp_time.trim()).map_err(|e| e.to_string())?;
+        Ok(LatencySlo {
+            percentile,
+            max_resp_time,
+        })
+    }
+}
+
+impl Display for LatencySlo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "p{}<{:?}", self.percentile, self.max_resp_time)
+    }
+}
+
+/// Parses a percentage given with or without the `%` sign
+fn parse_percent(s: &str) -> Result<f64, String> {
+    s.trim_end_matches('%')
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid percentage {s}: {e}"))
+}
+
+/// Parses a number greater than zero
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value = s
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid number {s}: {e}"))?;
+    positive(value, s)
+}
+
+fn positive(value: f64, s: &str) -> Result<f64, String> {
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("Value {s} must be greater than zero"))
+    }
+}
+
+/// Controls the search of the highest rate meeting a response time objective
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct SearchConf {
+    /// Searches for the highest rate meeting given response time objective, e.g. p99<10ms,
+    /// instead of running at a fixed rate.
+    /// Executes a series of runs of the configured duration at different rates.
+    #[clap(
+        long("slo"),
+        value_name = "PERCENTILE<TIME",
+        conflicts_with_all = ["rate", "rate_profile", "phases"]
+    )]
+    pub slo: Option<LatencySlo>,
+
+    /// Maximum percentage of failed requests allowed by the objective
+    #[clap(
+        long("slo-max-errors"),
+        default_value = "0",
+        value_name = "PERCENT",
+        value_parser = parse_percent
+    )]
+    pub max_error_ratio: f64,
+
+    /// Rate of the first run of the search
+    #[clap(
+        long("search-min-rate"),
+        default_value = "100",
+        value_name = "COUNT",
+        value_parser = parse_positive
+    )]
+    pub min_rate: f64,
+
+    /// Rate not exceeded by the search
+    #[clap(long("search-max-rate"), value_name = "COUNT", value_parser = parse_positive)]
+    pub max_rate: Option<f64>,
+
+    /// Stops the search when the lowest failing rate is within given percentage
+    /// above the highest passing rate
+    #[clap(
+        long("search-precision"),
+        default_value = "5",
+        value_name = "PERCENT",
+        value_parser = |s: &str| parse_percent(s).and_then(|p| positive(p, s))
+    )]
+    pub precision: f64,
+}
+
+impl SearchConf {
+    /// Checks the consistency of the search settings.
+    /// Returns the description of the problem if the settings are inconsistent.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.max_rate {
+            Some(max_rate) if max_rate < self.min_rate => Err(format!(
+                "Maximum search rate {max_rate} is lower than the minimum search rate {}",
+                self.min_rate
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for SearchConf {
+    fn default() -> Self {
+        SearchConf {
+            slo: None,
+            max_error_ratio: 0.0,
+            min_rate: 100.0,
+            max_rate: None,
+            precision: 5.0,
+        }
+    }
+}
+
+/// Kinds of errors of the workload cycles that the run can continue after
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum ErrorKind {
+    /// Timeouts, overload, unavailable replicas and other errors likely to be temporary
+    Transient,
+    /// Server overload or request timeout
+    Overloaded,
+    /// Any error returned by the driver or the server when executing a request,
+    /// e.g. a timeout, an invalid query or a write failure
+    QueryExecution,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Transient => write!(f, "transient"),
+            ErrorKind::Overloaded => write!(f, "overloaded"),
+            ErrorKind::QueryExecution => write!(f, "query-execution"),
+        }
+    }
+}
+
+/// Controls which errors stop the run
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorConf {
+    /// Kinds of errors the run continues after; the failed requests are counted as errors.
+    /// Other errors stop the run immediately.
+    #[clap(
+        long("tolerate-errors"),
+        default_value = "overloaded",
+        value_name = "KIND,...",
+        value_delimiter = ','
+    )]
+    pub tolerate: Vec<ErrorKind>,
+
+    /// Stops the run when the number of failed requests exceeds given count
+    #[clap(long("max-errors"), value_name = "COUNT")]
+    pub max_errors: Option<u64>,
+
+    /// Stops the run when the percentage of failed requests exceeds given value
+    #[clap(
+        long("max-error-ratio"),
+        id = "run_max_error_ratio",
+        value_name = "PERCENT",
+        value_parser = parse_percent
+    )]
+    pub max_error_ratio: Option<f64>,
+}
+
+impl ErrorConf {
+    /// Checks the counts of errors and requests against the limits.
+    /// The `request_count` includes the failed requests.
+    /// Returns the description of the exceeded limit or `None` if the limits are not exceeded.
+    pub fn budget_exceeded(&self, error_count: u64, request_count: u64) -> Option<String> {
+        let error_ratio = 100.0 * error_count as f64 / request_count as f64;
+        match (self.max_errors, self.max_error_ratio) {
+            (Some(max), _) if error_count > max => {
+                Some(format!("{error_count} errors exceed the limit of {max}"))
+            }
+            (_, Some(max)) if error_ratio > max => Some(format!(
+                "{error_ratio:.2}% failed requests exceed the limit of {max}%"
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ErrorConf {
+    fn default() -> Self {
+        ErrorConf {
+            tolerate: vec![ErrorKind::Overloaded],
+            max_errors: None,
+            max_error_ratio: None,
+        }
+    }
+}
+
+impl Display for ErrorConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tolerate {}", self.tolerate.iter().join(","))?;
+        if let Some(max) = self.max_errors {
+            write!(f, ", max {max} errors")?;
+        }
+        if let Some(max) = self.max_error_ratio {
+            write!(f, ", max {max}% errors")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SearchConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.slo {
+            Some(slo) => write!(f, "{slo}, errors<={}%", self.max_error_ratio),
+            None => write!(f, "disabled"),
+        }
+    }
+}
+
+/// Definition of a single phase of a scenario run.
+/// Options that are not set are taken from the run command.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseConf {
+    pub name: String,
+    pub functions: Vec<WeightedFunction>,
+    pub duration: Option<Interval>,
+    pub rate: Option<f64>,
+    pub concurrency: Option<NonZeroUsize>,
+    /// Time to wait after the phase before starting the next one
+    pub pause: Option<Duration>,
+}
+
+/// Parses phase definition in the NAME:KEY=VALUE,... format.
+/// The `function` option can be given multiple times to run a mix of functions.
+impl FromStr for PhaseConf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, options) = s.split_once(':').unwrap_or((s, ""));
+        if name.is_empty() {
+            return Err("Phase name must not be empty".to_string());
+        }
+        let mut phase = PhaseConf {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid phase option {option}: no `=` found"))?;
+            match key {
+                "function" | "f" => phase.functions.push(value.parse()?),
+                "duration" | "d" => phase.duration = Some(value.parse()?),
+                "rate" | "r" => {
+                    let rate = value
+                        .parse()
+                        .map_err(|e| format!("Invalid rate {value}: {e}"))?;
+                    phase.rate = Some(rate)
+                }
+                "concurrency" | "p" => {
+                    let concurrency = value
+                        .parse()
+                        .map_err(|e| format!("Invalid concurrency {value}: {e}"))?;
+                    phase.concurrency = Some(concurrency)
+                }
+                "pause" => {
+                    let pause = parse_duration::parse(value).map_err(|e| e.to_string())?;
+                    phase.pause = Some(pause)
+                }
+                _ => return Err(format!("Unknown phase option {key}")),
+            }
+        }
+        Ok(phase)
+    }
+}
+
+/// Formats the options that are set as a comma-separated list of `key=value` pairs.
+impl Display for PhaseConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut options = Vec::new();
+        for function in &self.functions {
+            options.push(format!("function={function}"));
+        }
+        if let Some(d) = &self.duration {
+            options.push(format!("duration={d}"));
+        }
+        if let Some(r) = &self.rate {
+            options.push(format!("rate={r}"));
+        }
+        if let Some(p) = &self.concurrency {
+            options.push(format!("concurrency={p}"));
+        }
+        if let Some(p) = &self.pause {
+            options.push(format!("pause={p:?}"));
+        }
+        write!(f, "{}", options.join(", "))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum Compression {
+    Lz4,
+    Snappy,
+}
+
+impl Compression {
+    pub fn scylla_compression(&self) -> scylla::transport::Compression {
+        match self {
+            Self::Lz4 => scylla::transport::Compression::Lz4,
+            Self::Snappy => scylla::transport::Compression::Snappy,
+        }
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lz4 => write!(f, "lz4"),
+            Self::Snappy => write!(f, "snappy"),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -200,6 +1030,279 @@ impl ValueEnum for Consistency {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SerialConsistency {
+    Serial,
+    LocalSerial,
+}
+
+impl SerialConsistency {
+    pub fn scylla_serial_consistency(&self) -> scylla::frame::types::SerialConsistency {
+        match self {
+            Self::Serial => scylla::frame::types::SerialConsistency::Serial,
+            Self::LocalSerial => scylla::frame::types::SerialConsistency::LocalSerial,
+        }
+    }
+}
+
+impl ValueEnum for SerialConsistency {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Serial, Self::LocalSerial]
+    }
+
+    fn from_str(s: &str, _ignore_case: bool) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "serial" | "s" => Ok(Self::Serial),
+            "local_serial" | "localserial" | "ls" => Ok(Self::LocalSerial),
+            s => Err(format!("Unknown serial consistency level {s}")),
+        }
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Serial => Some(PossibleValue::new("SERIAL")),
+            Self::LocalSerial => Some(PossibleValue::new("LOCAL_SERIAL")),
+        }
+    }
+}
+
+/// Retry policy used by the driver to decide whether a failed request should be retried.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DriverRetryPolicy {
+    /// Retries on timeouts and unavailable nodes, if there is a chance of success
+    Default,
+    /// Never retries
+    Fallthrough,
+    /// Retries with a lower consistency level if not enough replicas responded
+    Downgrading,
+}
+
+impl FromStr for DriverRetryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "fallthrough" | "none" => Ok(Self::Fallthrough),
+            "downgrading" | "downgrading_consistency" => Ok(Self::Downgrading),
+            s => Err(format!("Unknown retry policy {s}")),
+        }
+    }
+}
+
+impl Display for DriverRetryPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Fallthrough => write!(f, "fallthrough"),
+            Self::Downgrading => write!(f, "downgrading"),
+        }
+    }
+}
+
+/// Controls when the driver sends additional requests to other replicas
+/// before the response to the original request arrives.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpeculativeExecution {
+    /// Sends another request after a fixed delay
+    Constant(Duration),
+    /// Sends another request if the response takes longer than given percentile
+    /// of the response times observed for the node
+    Percentile(f64),
+}
+
+/// A string starting with `p` is parsed as a percentile, e.g. "p99".
+/// Otherwise the string is parsed as a time duration, e.g. "10ms".
+impl FromStr for SpeculativeExecution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(p) = s.strip_prefix('p').and_then(|p| p.parse::<f64>().ok()) {
+            if p > 0.0 && p < 100.0 {
+                Ok(Self::Percentile(p))
+            } else {
+                Err(format!("Percentile out of range (0, 100): {p}"))
+            }
+        } else if let Ok(d) = parse_duration::parse(s) {
+            Ok(Self::Constant(d))
+        } else {
+            Err("Required time duration or percentile, e.g. 10ms or p99".to_string())
+        }
+    }
+}
+
+impl Display for SpeculativeExecution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Constant(d) => write!(f, "{d:?}"),
+            Self::Percentile(p) => write!(f, "p{p}"),
+        }
+    }
+}
+
+/// Definition of a named execution profile.
+/// Options that are not set are taken from the connection settings.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionProfileConf {
+    pub name: String,
+    pub consistency: Option<Consistency>,
+    pub serial_consistency: Option<SerialConsistency>,
+    pub request_timeout: Option<Duration>,
+    pub retry: Option<DriverRetryPolicy>,
+    pub datacenter: Option<String>,
+    pub speculative: Option<SpeculativeExecution>,
+    pub speculative_retries: Option<usize>,
+}
+
+/// Parses profile definition in the NAME:KEY=VALUE,... format.
+impl FromStr for ExecutionProfileConf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, options) = s.split_once(':').unwrap_or((s, ""));
+        if name.is_empty() {
+            return Err("Profile name must not be empty".to_string());
+        }
+        let mut profile = ExecutionProfileConf {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid profile option {option}: no `=` found"))?;
+            match key {
+                "consistency" => profile.consistency = Some(Consistency::from_str(value, true)?),
+                "serial_consistency" => {
+                    profile.serial_consistency = Some(SerialConsistency::from_str(value, true)?)
+                }
+                "timeout" => {
+                    let timeout = parse_duration::parse(value).map_err(|e| e.to_string())?;
+                    profile.request_timeout = Some(timeout)
+                }
+                "retry" => profile.retry = Some(value.parse()?),
+                "datacenter" | "dc" => profile.datacenter = Some(value.to_string()),
+                "speculative" => profile.speculative = Some(value.parse()?),
+                "speculative_retries" => {
+                    let retries = value.parse().map_err(|e| format!("{e}"))?;
+                    profile.speculative_retries = Some(retries)
+                }
+                _ => return Err(format!("Unknown profile option {key}")),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// Formats the options that are set as a comma-separated list of `key=value` pairs.
+impl Display for ExecutionProfileConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut options = Vec::new();
+        if let Some(c) = &self.consistency {
+            options.push(format!(
+                "consistency={}",
+                c.to_possible_value().unwrap().get_name()
+            ));
+        }
+        if let Some(c) = &self.serial_consistency {
+            options.push(format!(
+                "serial_consistency={}",
+                c.to_possible_value().unwrap().get_name()
+            ));
+        }
+        if let Some(t) = &self.request_timeout {
+            options.push(format!("timeout={t:?}"));
+        }
+        if let Some(r) = &self.retry {
+            options.push(format!("retry={r}"));
+        }
+        if let Some(dc) = &self.datacenter {
+            options.push(format!("datacenter={dc}"));
+        }
+        if let Some(s) = &self.speculative {
+            options.push(format!("speculative={s}"));
+        }
+        if let Some(r) = &self.speculative_retries {
+            options.push(format!("speculative_retries={r}"));
+        }
+        write!(f, "{}", options.join(", "))
+    }
+}
+
+/// Execution options of a single prepared statement.
+/// Options that are not set fall back to the defaults of the connection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatementOptions {
+    pub profile: Option<String>,
+    pub consistency: Option<Consistency>,
+    pub serial_consistency: Option<SerialConsistency>,
+    pub request_timeout: Option<Duration>,
+    pub idempotent: Option<bool>,
+    pub page_size: Option<i32>,
+    pub timestamp: Option<i64>,
+    pub tracing: Option<bool>,
+    pub speculative: Option<SpeculativeExecution>,
+    pub speculative_retries: Option<usize>,
+}
+
+impl StatementOptions {
+    pub fn is_empty(&self) -> bool {
+        self == &StatementOptions::default()
+    }
+}
+
+/// Formats the options that are set as a comma-separated list of `key=value` pairs.
+impl Display for StatementOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut options = Vec::new();
+        if let Some(p) = &self.profile {
+            options.push(format!("profile={p}"));
+        }
+        if let Some(c) = &self.consistency {
+            options.push(format!(
+                "consistency={}",
+                c.to_possible_value().unwrap().get_name()
+            ));
+        }
+        if let Some(c) = &self.serial_consistency {
+            options.push(format!(
+                "serial_consistency={}",
+                c.to_possible_value().unwrap().get_name()
+            ));
+        }
+        if let Some(t) = &self.request_timeout {
+            options.push(format!("timeout={t:?}"));
+        }
+        if let Some(i) = &self.idempotent {
+            options.push(format!("idempotent={i}"));
+        }
+        if let Some(p) = &self.page_size {
+            options.push(format!("page_size={p}"));
+        }
+        if let Some(t) = &self.timestamp {
+            options.push(format!("timestamp={t}"));
+        }
+        if let Some(t) = &self.tracing {
+            options.push(format!("tracing={t}"));
+        }
+        if let Some(s) = &self.speculative {
+            options.push(format!("speculative={s}"));
+        }
+        if let Some(r) = &self.speculative_retries {
+            options.push(format!("speculative_retries={r}"));
+        }
+        write!(f, "{}", options.join(", "))
+    }
+}
+
+/// A statement prepared by the workload, recorded in the report.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatementConf {
+    pub cql: String,
+    #[serde(default)]
+    pub options: StatementOptions,
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize)]
 #[command(next_line_help = true)]
 pub struct SchemaCommand {
@@ -257,6 +1360,26 @@ pub struct RunCommand {
     #[clap(short('r'), long, value_name = "COUNT")]
     pub rate: Option<f64>,
 
+    /// Varies the number of cycles per second over the time of the benchmark.
+    /// Available profiles: `ramp:FROM,TO` (linear change over the duration),
+    /// `steps:RATE,RATE,...` (equal-length steps over the duration),
+    /// `sine:MEAN,AMPLITUDE,PERIOD` and `csv:PATH` (linear interpolation between
+    /// TIME_SECONDS,RATE points read from a file).
+    #[clap(long("rate-profile"), value_name = "PROFILE", conflicts_with = "rate")]
+    #[serde(default)]
+    pub rate_profile: Option<RateProfile>,
+
+    /// Distribution of the intervals between the starts of the cycles when the rate is limited.
+    #[clap(long, default_value = "fixed", value_name = "DISTRIBUTION")]
+    #[serde(default)]
+    pub arrivals: Arrivals,
+
+    /// Seed of the random intervals between the starts of the cycles.
+    /// Runs with the same seed schedule the cycles at the same times.
+    #[clap(long("arrival-seed"), default_value = "0", value_name = "NUMBER")]
+    #[serde(default)]
+    pub arrival_seed: u64,
+
     /// Number of cycles or duration of the warmup phase.
     #[clap(
         short('w'),
@@ -283,6 +1406,24 @@ pub struct RunCommand {
     #[clap(short('p'), long, default_value = "128", value_name = "COUNT")]
     pub concurrency: NonZeroUsize,
 
+    /// Launches cycles at their scheduled times regardless of the number of cycles in progress,
+    /// so the rate doesn't drop when the server slows down. The concurrency limit is ignored.
+    /// Requires a rate or a rate profile.
+    #[clap(long("open-loop"))]
+    #[serde(default)]
+    pub open_loop: bool,
+
+    /// Adjusts the number of concurrent requests per thread after each sample, so that
+    /// the response time percentile stays below the given time, e.g. `p99<10ms`.
+    /// The concurrency level set with `-p` becomes the upper limit.
+    #[clap(
+        long("adaptive-concurrency"),
+        value_name = "OBJECTIVE",
+        conflicts_with = "open_loop"
+    )]
+    #[serde(default)]
+    pub adaptive_concurrency: Option<LatencySlo>,
+
     /// Throughput sampling period, in seconds.
     #[clap(
         short('s'),
@@ -309,9 +1450,31 @@ pub struct RunCommand {
     #[clap(name = "workload", required = true, value_name = "PATH")]
     pub workload: PathBuf,
 
-    /// Function of the workload to invoke.
-    #[clap(long, short('f'), required = false, default_value = "run")]
-    pub function: String,
+    /// Function of the workload to invoke, optionally followed by a weight, e.g. `read:0.8`.
+    /// Can be given multiple times to run a mix of functions.
+    /// Each cycle invokes one function picked by a deterministic weighted choice based on
+    /// the cycle number.
+    #[clap(
+        long("function"),
+        short('f'),
+        required = false,
+        default_value = "run",
+        number_of_values = 1,
+        value_name = "NAME[:WEIGHT]"
+    )]
+    #[serde(alias = "function", deserialize_with = "deserialize_functions")]
+    pub functions: Vec<WeightedFunction>,
+
+    /// Runs a phase of a multi-phase scenario instead of a single benchmark phase.
+    /// Phases are executed in the order given, against the same session, and their
+    /// statistics are reported separately and on one shared timeline.
+    /// Available options: function (NAME[:WEIGHT], can be repeated),
+    /// duration (TIME | COUNT), rate, concurrency and pause (TIME to wait after the phase).
+    /// Options not given are taken from the run command.
+    /// Can be used multiple times.
+    #[clap(long("phase"), value_name = "NAME:KEY=VALUE,...", number_of_values = 1)]
+    #[serde(default)]
+    pub phases: Vec<PhaseConf>,
 
     /// Parameter values passed to the workload, accessible through param! macro.
     #[clap(short('P'), value_parser = parse_key_val::<String, String>, number_of_values = 1)]
@@ -321,6 +1484,21 @@ pub struct RunCommand {
     #[clap(short, long)]
     pub quiet: bool,
 
+    // Capturing server-side traces of requests.
+    #[clap(flatten)]
+    #[serde(default)]
+    pub tracing: TracingConf,
+
+    // Searching for the maximum sustainable throughput.
+    #[clap(flatten)]
+    #[serde(default)]
+    pub search: SearchConf,
+
+    // Tolerating errors.
+    #[clap(flatten)]
+    #[serde(default)]
+    pub errors: ErrorConf,
+
     // Cassandra connection settings.
     #[clap(flatten)]
     pub connection: ConnectionConf,
@@ -334,6 +1512,11 @@ pub struct RunCommand {
 
     #[clap(skip)]
     pub cass_version: Option<String>,
+
+    /// Statements prepared by the workload, together with their execution options
+    #[clap(skip)]
+    #[serde(default)]
+    pub statements: BTreeMap<String, StatementConf>,
 }
 
 impl RunCommand {
@@ -362,6 +1545,36 @@ impl RunCommand {
             .to_string()
     }
 
+    /// Returns the phases to run, with the options not set in the phase definition
+    /// filled from the run command.
+    /// If no phases are defined, returns a single phase named `main`.
+    pub fn resolved_phases(&self) -> Vec<PhaseConf> {
+        let main = PhaseConf {
+            name: "main".to_string(),
+            ..Default::default()
+        };
+        let phases = if self.phases.is_empty() {
+            std::slice::from_ref(&main)
+        } else {
+            self.phases.as_slice()
+        };
+        phases
+            .iter()
+            .map(|p| PhaseConf {
+                name: p.name.clone(),
+                functions: if p.functions.is_empty() {
+                    self.functions.clone()
+                } else {
+                    p.functions.clone()
+                },
+                duration: p.duration.or(Some(self.run_duration)),
+                rate: p.rate.or(self.rate),
+                concurrency: p.concurrency.or(Some(self.concurrency)),
+                pause: p.pause,
+            })
+            .collect()
+    }
+
     /// Suggested file name where to save the results of the run.
     pub fn default_output_file_name(&self, extension: &str) -> PathBuf {
         let mut components = vec![self.name()];
@@ -516,3 +1729,212 @@ pub struct WorkloadConfig {
     #[serde(default)]
     pub bindings: HashMap<String, String>,
 }
+
+#[cfg(test)]
+mod test {
+    use clap::{CommandFactory, Parser};
+
+    use crate::config::{
+        AppConfig, Arrivals, Command, Consistency, DriverRetryPolicy, ErrorConf,
+        ExecutionProfileConf, Interval, LatencySlo, PhaseConf, RateProfile, RetryConf, RetryPolicy,
+        SearchConf, SpeculativeExecution, TracingConf, WeightedFunction,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    #[test]
+    pub fn parse_execution_profile() {
+        let profile: ExecutionProfileConf =
+            "fast:consistency=LOCAL_ONE,timeout=500ms,retry=fallthrough,speculative=p99"
+                .parse()
+                .unwrap();
+        assert_eq!(profile.name, "fast");
+        assert_eq!(profile.consistency, Some(Consistency::LocalOne));
+        assert_eq!(profile.request_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(profile.retry, Some(DriverRetryPolicy::Fallthrough));
+        assert_eq!(
+            profile.speculative,
+            Some(SpeculativeExecution::Percentile(99.0))
+        );
+        assert_eq!(profile.datacenter, None);
+    }
+
+    #[test]
+    pub fn parse_execution_profile_without_options() {
+        let profile: ExecutionProfileConf = "default".parse().unwrap();
+        assert_eq!(profile.name, "default");
+        assert_eq!(profile.consistency, None);
+    }
+
+    #[test]
+    pub fn reject_unknown_execution_profile_option() {
+        assert!("p:foo=bar".parse::<ExecutionProfileConf>().is_err());
+        assert!(":consistency=ONE".parse::<ExecutionProfileConf>().is_err());
+    }
+
+    #[test]
+    pub fn exponential_retry_delay_is_capped() {
+        let conf = RetryConf {
+            policy: RetryPolicy::Exponential,
+            max_retries: 4,
+            delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            retry_non_idempotent: false,
+        };
+        assert_eq!(conf.delay(1), Some(Duration::from_millis(100)));
+        assert_eq!(conf.delay(2), Some(Duration::from_millis(200)));
+        assert_eq!(conf.delay(3), Some(Duration::from_millis(300)));
+        assert_eq!(conf.delay(4), Some(Duration::from_millis(300)));
+        assert_eq!(conf.delay(5), None);
+    }
+
+    #[test]
+    pub fn no_retry_delay_without_retry_policy() {
+        let conf = RetryConf::default();
+        assert_eq!(conf.delay(1), None);
+    }
+
+    #[test]
+    pub fn command_line_args_are_consistent() {
+        AppConfig::command().debug_assert();
+    }
+
+    #[test]
+    pub fn trace_all_requests_above_threshold() {
+        let mut conf = TracingConf::default();
+        assert!(!conf.is_enabled());
+        conf.threshold = Some(Duration::from_millis(50));
+        assert!(conf.is_enabled());
+        assert_eq!(conf.sample_ratio(), 1.0);
+        conf.sample = Some(0.01);
+        assert_eq!(conf.sample_ratio(), 0.01);
+    }
+
+    #[test]
+    pub fn reject_trace_sample_out_of_range() {
+        let parse =
+            |s| TracingConf::try_parse_from(["latte".to_string(), format!("--trace-sample={s}")]);
+        assert_eq!(parse("0.5").unwrap().sample, Some(0.5));
+        assert!(parse("1.5").is_err());
+        assert!(parse("-0.1").is_err());
+        assert!(parse("NaN").is_err());
+    }
+
+    #[test]
+    pub fn reject_invalid_search_settings() {
+        let parse = |args: &[&str]| -> Result<SearchConf, clap::Error> {
+            let args = ["latte", "run", "workload.rn"].iter().chain(args);
+            match AppConfig::try_parse_from(args)?.command {
+                Command::Run(run) => Ok(run.search),
+                _ => unreachable!(),
+            }
+        };
+        assert!(parse(&["--search-min-rate", "0"]).is_err());
+        assert!(parse(&["--search-max-rate=-100"]).is_err());
+        assert!(parse(&["--search-precision", "0"]).is_err());
+        assert!(parse(&["--search-precision=-5%"]).is_err());
+        let conf = parse(&["--search-min-rate", "1000", "--search-max-rate", "500"]).unwrap();
+        assert!(conf.validate().is_err());
+        let conf = parse(&["--search-min-rate", "1000", "--search-max-rate", "1000"]).unwrap();
+        assert!(conf.validate().is_ok());
+    }
+
+    #[test]
+    pub fn parse_phase() {
+        let phase: PhaseConf = "reads:function=read:0.8,function=write:0.2,duration=5m,rate=1000"
+            .parse()
+            .unwrap();
+        assert_eq!(phase.name, "reads");
+        assert_eq!(phase.functions.len(), 2);
+        assert_eq!(phase.functions[1].name, "write");
+        assert_eq!(phase.functions[1].weight, 0.2);
+        assert_eq!(
+            phase.duration,
+            Some(Interval::Time(Duration::from_secs(300)))
+        );
+        assert_eq!(phase.rate, Some(1000.0));
+        assert_eq!(phase.concurrency, None);
+        assert!("p:foo=bar".parse::<PhaseConf>().is_err());
+    }
+
+    #[test]
+    pub fn rate_profiles() {
+        let d = Some(Duration::from_secs(100));
+        let ramp: RateProfile = "ramp:1000,2000".parse().unwrap();
+        assert_eq!(ramp.rate_at(Duration::ZERO, d), 1000.0);
+        assert_eq!(ramp.rate_at(Duration::from_secs(50), d), 1500.0);
+        assert_eq!(ramp.rate_at(Duration::from_secs(200), d), 2000.0);
+
+        let steps: RateProfile = "steps:100,200,300,400".parse().unwrap();
+        assert_eq!(steps.rate_at(Duration::from_secs(24), d), 100.0);
+        assert_eq!(steps.rate_at(Duration::from_secs(25), d), 200.0);
+        assert_eq!(steps.rate_at(Duration::from_secs(100), d), 400.0);
+
+        let sine: RateProfile = "sine:1000,500,60s".parse().unwrap();
+        assert_eq!(sine.rate_at(Duration::ZERO, None), 1000.0);
+        assert!((sine.rate_at(Duration::from_secs(15), None) - 1500.0).abs() < 0.001);
+
+        let points = RateProfile::Points {
+            path: "profile.csv".into(),
+            points: vec![(10.0, 100.0), (20.0, 300.0)],
+        };
+        assert_eq!(points.rate_at(Duration::ZERO, None), 100.0);
+        assert_eq!(points.rate_at(Duration::from_secs(15), None), 200.0);
+        assert_eq!(points.rate_at(Duration::from_secs(30), None), 300.0);
+
+        assert!("ramp:1000".parse::<RateProfile>().is_err());
+        assert!("steps:100,-1".parse::<RateProfile>().is_err());
+        assert!("square:1,2".parse::<RateProfile>().is_err());
+    }
+
+    #[test]
+    pub fn parse_latency_slo() {
+        let slo: LatencySlo = "p99.9<10ms".parse().unwrap();
+        assert_eq!(slo.percentile, 99.9);
+        assert_eq!(slo.max_resp_time, Duration::from_millis(10));
+        assert!("99<10ms".parse::<LatencySlo>().is_err());
+        assert!("p99>10ms".parse::<LatencySlo>().is_err());
+        assert!("p101<10ms".parse::<LatencySlo>().is_err());
+    }
+
+    #[test]
+    pub fn parse_weighted_function() {
+        let f: WeightedFunction = "read".parse().unwrap();
+        assert_eq!(f.name, "read");
+        assert_eq!(f.weight, 1.0);
+        let f: WeightedFunction = "write:0.2".parse().unwrap();
+        assert_eq!(f.name, "write");
+        assert_eq!(f.weight, 0.2);
+        assert!("write:0".parse::<WeightedFunction>().is_err());
+        assert!("write:-1".parse::<WeightedFunction>().is_err());
+        assert!(":1".parse::<WeightedFunction>().is_err());
+    }
+
+    #[test]
+    pub fn error_budget() {
+        let conf = ErrorConf {
+            max_errors: Some(100),
+            max_error_ratio: Some(1.0),
+            ..Default::default()
+        };
+        assert!(conf.budget_exceeded(0, 0).is_none());
+        assert!(conf.budget_exceeded(50, 10000).is_none());
+        assert!(conf.budget_exceeded(101, 1000000).is_some());
+        assert!(conf.budget_exceeded(50, 1000).is_some());
+        assert!(ErrorConf::default().budget_exceeded(1000, 1000).is_none());
+    }
+
+    #[test]
+    pub fn arrival_intervals_average_to_one() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for arrivals in [Arrivals::Fixed, Arrivals::Poisson, Arrivals::Uniform] {
+            let n = 100000;
+            let mean = (0..n)
+                .map(|_| arrivals.next_interval(&mut rng))
+                .sum::<f64>()
+                / n as f64;
+            assert!((mean - 1.0).abs() < 0.01, "{arrivals}: {mean}");
+        }
+    }
+}
@@ -1,13 +1,18 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::fs::File;
+use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::io;
 use std::io::{BufRead, BufReader, ErrorKind, Read};
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
-use chrono::Utc;
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
 use hdrhistogram::Histogram;
 use itertools::Itertools;
 use metrohash::{MetroHash128, MetroHash64};
@@ -23,17 +28,38 @@ use rune::parse::Parser;
 use rune::runtime::{Object, Shared, TypeInfo, VmError};
 use rune::{Any, Value};
 use rust_embed::RustEmbed;
-use scylla::frame::response::result::CqlValue;
+use scylla::batch::{BatchStatement, BatchType};
+use scylla::execution_profile::{ExecutionProfileBuilder, ExecutionProfileHandle};
+use scylla::frame::response::result::{ColumnSpec, CqlValue};
+use scylla::frame::value::{
+    CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
+};
+use scylla::history::{AttemptId, HistoryListener, QueryId, SpeculativeId};
+use scylla::load_balancing::{DefaultPolicy, LoadBalancingPolicy};
 use scylla::prepared_statement::PreparedStatement;
+use scylla::query::Query;
+use scylla::retry_policy::{
+    DefaultRetryPolicy, FallthroughRetryPolicy, RetryDecision, RetryPolicy,
+};
+use scylla::speculative_execution::{
+    PercentileSpeculativeExecutionPolicy, SimpleSpeculativeExecutionPolicy,
+    SpeculativeExecutionPolicy,
+};
+use scylla::transport::downgrading_consistency_retry_policy::DowngradingConsistencyRetryPolicy;
 use scylla::transport::errors::{DbError, NewSessionError, QueryError};
 use scylla::transport::session::PoolSize;
 use scylla::{ExecutionProfile, QueryResult, SessionBuilder};
 use statrs::distribution::Normal;
-use tokio::time::{Duration, Instant};
+use tokio::time::Instant;
 use try_lock::TryLock;
 use uuid::{Variant, Version};
 
-use crate::config::ConnectionConf;
+use crate::config::{
+    ConnectionConf, DriverRetryPolicy, ExecutionProfileConf, RetryConf, SpeculativeExecution,
+    StatementConf, StatementOptions, TracingConf,
+};
+use crate::stats::{ErrorCategory, ErrorStats};
+use crate::trace::{self, RequestTrace, TracedRequest};
 use crate::LatteError;
 
 fn ssl_context(conf: &&ConnectionConf) -> Result<Option<SslContext>, CassError> {
@@ -54,18 +80,116 @@ fn ssl_context(conf: &&ConnectionConf) -> Result<Option<SslContext>, CassError>
     }
 }
 
+/// Returns the load balancing policy preferring given datacenter and rack.
+fn load_balancing_policy(
+    conf: &ConnectionConf,
+    datacenter: Option<&String>,
+    rack: Option<&String>,
+) -> Arc<dyn LoadBalancingPolicy> {
+    let builder = DefaultPolicy::builder().token_aware(!conf.no_token_aware);
+    let builder = match (datacenter, rack) {
+        (Some(dc), Some(rack)) => builder.prefer_datacenter_and_rack(dc.clone(), rack.clone()),
+        (Some(dc), None) => builder.prefer_datacenter(dc.clone()),
+        _ => builder,
+    };
+    builder.build()
+}
+
+/// Returns the execution profile builder configured according to the connection settings.
+fn default_profile_builder(conf: &ConnectionConf) -> ExecutionProfileBuilder {
+    ExecutionProfile::builder()
+        .load_balancing_policy(load_balancing_policy(
+            conf,
+            conf.datacenter.as_ref(),
+            conf.rack.as_ref(),
+        ))
+        .consistency(conf.consistency.scylla_consistency())
+        .request_timeout(Some(std::time::Duration::from_secs(60))) // no request timeout
+        .speculative_execution_policy(
+            conf.speculative
+                .map(|s| speculative_execution_policy(s, conf.speculative_retries)),
+        )
+}
+
+fn retry_policy(policy: DriverRetryPolicy) -> Box<dyn RetryPolicy> {
+    match policy {
+        DriverRetryPolicy::Default => Box::new(DefaultRetryPolicy::new()),
+        DriverRetryPolicy::Fallthrough => Box::new(FallthroughRetryPolicy::new()),
+        DriverRetryPolicy::Downgrading => Box::new(DowngradingConsistencyRetryPolicy::new()),
+    }
+}
+
+fn speculative_execution_policy(
+    speculative: SpeculativeExecution,
+    max_retry_count: usize,
+) -> Arc<dyn SpeculativeExecutionPolicy> {
+    match speculative {
+        SpeculativeExecution::Constant(retry_interval) => {
+            Arc::new(SimpleSpeculativeExecutionPolicy {
+                max_retry_count,
+                retry_interval,
+            })
+        }
+        SpeculativeExecution::Percentile(percentile) => {
+            Arc::new(PercentileSpeculativeExecutionPolicy {
+                max_retry_count,
+                percentile,
+            })
+        }
+    }
+}
+
+/// Builds an execution profile from its definition.
+/// Options missing in the definition are taken from the connection settings.
+fn execution_profile(conf: &ConnectionConf, profile: &ExecutionProfileConf) -> ExecutionProfile {
+    let mut builder = default_profile_builder(conf);
+    if let Some(c) = profile.consistency {
+        builder = builder.consistency(c.scylla_consistency());
+    }
+    if let Some(c) = profile.serial_consistency {
+        builder = builder.serial_consistency(Some(c.scylla_serial_consistency()));
+    }
+    if let Some(t) = profile.request_timeout {
+        builder = builder.request_timeout(Some(t));
+    }
+    if let Some(r) = profile.retry {
+        builder = builder.retry_policy(retry_policy(r));
+    }
+    if let Some(dc) = &profile.datacenter {
+        builder = builder.load_balancing_policy(load_balancing_policy(conf, Some(dc), None));
+    }
+    if let Some(s) = profile.speculative {
+        let max_retry_count = profile
+            .speculative_retries
+            .unwrap_or(conf.speculative_retries);
+        builder = builder
+            .speculative_execution_policy(Some(speculative_execution_policy(s, max_retry_count)));
+    }
+    builder.build()
+}
+
+/// Builds the named execution profiles defined in the connection settings.
+fn execution_profiles(conf: &ConnectionConf) -> HashMap<String, ExecutionProfileHandle> {
+    conf.profiles
+        .iter()
+        .map(|p| {
+            let handle = execution_profile(conf, p).into_handle_with_label(p.name.clone());
+            (p.name.clone(), handle)
+        })
+        .collect()
+}
+
 /// Configures connection to Cassandra.
 pub async fn connect(conf: &ConnectionConf) -> Result<scylla::Session, CassError> {
-    let profile = ExecutionProfile::builder()
-        .consistency(conf.consistency.scylla_consistency())
-        .request_timeout(Some(Duration::from_secs(60))) // no request timeout
-        .build();
+    let profile = default_profile_builder(conf).build();
 
     SessionBuilder::new()
         .known_nodes(&conf.addresses)
         .pool_size(PoolSize::PerShard(conf.count))
         .user(&conf.user, &conf.password)
         .ssl_context(ssl_context(&conf)?)
+        .disallow_shard_aware_port(conf.no_shard_aware)
+        .compression(conf.compression.map(|c| c.scylla_compression()))
         .default_execution_profile_handle(profile.into_handle())
         .build()
         .await
@@ -90,17 +214,36 @@ impl CassError {
             cql: cql.to_string(),
             params: params.iter().map(|v| format!("{v:?}")).collect(),
         };
-        let kind = match err {
-            QueryError::RequestTimeout(_)
-            | QueryError::TimeoutError
-            | QueryError::DbError(
-                DbError::Overloaded | DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. },
-                _,
-            ) => CassErrorKind::Overloaded(query, err),
-            _ => CassErrorKind::QueryExecution(query, err),
+        let kind = if error_category(&err).is_overload() {
+            CassErrorKind::Overloaded(query, err)
+        } else {
+            CassErrorKind::QueryExecution(query, err)
         };
         CassError(kind)
     }
+
+    /// Returns true if the error is likely temporary, e.g. caused by a timeout,
+    /// overload or unavailable replicas, so the workload may continue despite it.
+    pub fn is_transient(&self) -> bool {
+        match &self.0 {
+            CassErrorKind::Overloaded(_, _) => true,
+            CassErrorKind::QueryExecution(_, e) => error_category(e).is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the error belongs to given kind of errors
+    pub fn is_of_kind(&self, kind: crate::config::ErrorKind) -> bool {
+        use crate::config::ErrorKind as K;
+        match kind {
+            K::Transient => self.is_transient(),
+            K::Overloaded => matches!(self.0, CassErrorKind::Overloaded(_, _)),
+            K::QueryExecution => matches!(
+                self.0,
+                CassErrorKind::Overloaded(_, _) | CassErrorKind::QueryExecution(_, _)
+            ),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -125,7 +268,13 @@ pub enum CassErrorKind {
     SslConfiguration(ErrorStack),
     FailedToConnect(Vec<String>, NewSessionError),
     PreparedStatementNotFound(String),
+    ExecutionProfileNotFound(String),
+    UnsupportedBatchType(String),
     UnsupportedType(TypeInfo),
+    InvalidQueryParamsCount(usize, usize),
+    InvalidQueryParam(String, String),
+    InvalidStatementOption(String, String),
+    NotConditional(String),
     Prepare(String, QueryError),
     Overloaded(QueryInfo, QueryError),
     QueryExecution(QueryInfo, QueryError),
@@ -144,9 +293,36 @@ impl CassError {
             CassErrorKind::PreparedStatementNotFound(s) => {
                 write!(buf, "Prepared statement not found: {s}")
             }
+            CassErrorKind::ExecutionProfileNotFound(s) => {
+                write!(buf, "Execution profile not found: {s}")
+            }
+            CassErrorKind::UnsupportedBatchType(s) => {
+                write!(
+                    buf,
+                    "Unsupported batch type: {s}; expected one of: logged, unlogged, counter"
+                )
+            }
             CassErrorKind::UnsupportedType(s) => {
                 write!(buf, "Unsupported type: {s}")
             }
+            CassErrorKind::InvalidQueryParamsCount(expected, actual) => {
+                write!(
+                    buf,
+                    "Invalid number of query parameters: expected {expected}, got {actual}"
+                )
+            }
+            CassErrorKind::InvalidQueryParam(column, msg) => {
+                write!(buf, "Invalid value of parameter {column}: {msg}")
+            }
+            CassErrorKind::InvalidStatementOption(key, msg) => {
+                write!(buf, "Invalid statement option {key}: {msg}")
+            }
+            CassErrorKind::NotConditional(key) => {
+                write!(
+                    buf,
+                    "Statement {key} is not conditional: its result has no [applied] column"
+                )
+            }
             CassErrorKind::Prepare(q, e) => {
                 write!(buf, "Failed to prepare query \"{q}\": {e}")
             }
@@ -179,12 +355,51 @@ impl std::error::Error for CassError {}
 #[derive(Clone, Debug)]
 pub struct SessionStats {
     pub req_count: u64,
-    pub req_errors: HashSet<String>,
+    pub req_errors: ErrorStats,
     pub req_error_count: u64,
+    pub retry_count: u64,
+    pub speculative_count: u64,
+    pub lwt_applied_count: u64,
+    pub lwt_rejected_count: u64,
     pub row_count: u64,
+    pub batch_count: u64,
+    pub batch_statement_count: u64,
     pub queue_length: u64,
     pub mean_queue_length: f32,
     pub resp_times_ns: Histogram<u64>,
+    pub attempts: Histogram<u64>,
+    /// Slowest requests executed with tracing enabled
+    pub traces: Vec<TracedRequest>,
+    /// Statistics of requests broken down by the prepared statement key
+    pub statements: HashMap<String, StatementStats>,
+}
+
+/// Statement key under which the requests of ad-hoc (unprepared) statements are recorded
+pub const AD_HOC_STATEMENT_KEY: &str = "<ad-hoc>";
+/// Statement key under which the batches are recorded
+pub const BATCH_STATEMENT_KEY: &str = "<batch>";
+
+/// Statistics of requests executed by a single statement
+#[derive(Clone, Debug)]
+pub struct StatementStats {
+    pub req_count: u64,
+    pub req_error_count: u64,
+    pub row_count: u64,
+    pub resp_times_ns: Histogram<u64>,
+    /// Counts of failed requests by the category of the error
+    pub errors: BTreeMap<ErrorCategory, u64>,
+}
+
+impl Default for StatementStats {
+    fn default() -> Self {
+        StatementStats {
+            req_count: 0,
+            req_error_count: 0,
+            row_count: 0,
+            resp_times_ns: Histogram::new(3).unwrap(),
+            errors: BTreeMap::new(),
+        }
+    }
 }
 
 impl SessionStats {
@@ -201,28 +416,82 @@ impl SessionStats {
         Instant::now()
     }
 
-    pub fn complete_request(&mut self, duration: Duration, rs: &Result<QueryResult, QueryError>) {
+    pub fn complete_request(
+        &mut self,
+        statement_key: &str,
+        duration: std::time::Duration,
+        attempts: u32,
+        rs: &Result<QueryResult, QueryError>,
+    ) {
         self.queue_length -= 1;
         let duration_ns = duration.as_nanos().clamp(1, u64::MAX as u128) as u64;
         self.resp_times_ns.record(duration_ns).unwrap();
+        self.attempts.record(attempts as u64).unwrap();
+        self.retry_count += attempts.saturating_sub(1) as u64;
         self.req_count += 1;
+
+        if !self.statements.contains_key(statement_key) {
+            self.statements
+                .insert(statement_key.to_string(), StatementStats::default());
+        }
+        let statement_stats = self.statements.get_mut(statement_key).unwrap();
+        statement_stats.req_count += 1;
+        statement_stats.resp_times_ns.record(duration_ns).unwrap();
+
         match rs {
-            Ok(rs) => self.row_count += rs.rows.as_ref().map(|r| r.len()).unwrap_or(0) as u64,
+            Ok(rs) => {
+                let row_count = rs.rows.as_ref().map(|r| r.len()).unwrap_or(0) as u64;
+                self.row_count += row_count;
+                statement_stats.row_count += row_count;
+                match lwt_applied(rs) {
+                    Some(true) => self.lwt_applied_count += 1,
+                    Some(false) => self.lwt_rejected_count += 1,
+                    None => {}
+                }
+            }
             Err(e) => {
+                let category = error_category(e);
                 self.req_error_count += 1;
-                self.req_errors.insert(format!("{e}"));
+                statement_stats.req_error_count += 1;
+                *statement_stats.errors.entry(category).or_default() += 1;
+                self.req_errors
+                    .record(category, || format!("{statement_key}: {e}"));
             }
         }
     }
 
+    /// Records a request executed with tracing enabled.
+    /// Keeps only `max_count` slowest requests.
+    pub fn record_trace(&mut self, request: TracedRequest, max_count: usize) {
+        self.traces.push(request);
+        if self.traces.len() > 2 * max_count {
+            trace::keep_slowest(&mut self.traces, max_count);
+        }
+    }
+
+    /// Records that the last completed request was a batch of `statement_count` statements
+    pub fn complete_batch(&mut self, statement_count: usize) {
+        self.batch_count += 1;
+        self.batch_statement_count += statement_count as u64;
+    }
+
     /// Resets all accumulators
     pub fn reset(&mut self) {
         self.req_error_count = 0;
+        self.retry_count = 0;
+        self.speculative_count = 0;
+        self.lwt_applied_count = 0;
+        self.lwt_rejected_count = 0;
         self.row_count = 0;
+        self.batch_count = 0;
+        self.batch_statement_count = 0;
         self.req_count = 0;
         self.mean_queue_length = 0.0;
-        self.req_errors.clear();
+        self.req_errors = ErrorStats::default();
         self.resp_times_ns.clear();
+        self.attempts.clear();
+        self.traces.clear();
+        self.statements.clear();
 
         // note that current queue_length is *not* reset to zero because there
         // might be pending requests and if we set it to zero, that would underflow
@@ -233,13 +502,191 @@ impl Default for SessionStats {
     fn default() -> Self {
         SessionStats {
             req_count: 0,
-            req_errors: HashSet::new(),
+            req_errors: ErrorStats::default(),
             req_error_count: 0,
+            retry_count: 0,
+            speculative_count: 0,
+            lwt_applied_count: 0,
+            lwt_rejected_count: 0,
             row_count: 0,
+            batch_count: 0,
+            batch_statement_count: 0,
             queue_length: 0,
             mean_queue_length: 0.0,
             resp_times_ns: Histogram::new(3).unwrap(),
+            attempts: Histogram::new(3).unwrap(),
+            traces: Vec::new(),
+            statements: HashMap::new(),
+        }
+    }
+}
+
+/// Returns the value of the `[applied]` flag if the result comes from a conditional statement
+/// (lightweight transaction) or `None` otherwise.
+fn lwt_applied(rs: &QueryResult) -> Option<bool> {
+    if rs.col_specs.first()?.name != "[applied]" {
+        return None;
+    }
+    match rs.rows.as_ref()?.first()?.columns.first()? {
+        Some(CqlValue::Boolean(applied)) => Some(*applied),
+        _ => None,
+    }
+}
+
+/// Classifies the error of a failed request by its cause.
+/// The category decides whether the error is transient and whether it signals an overload.
+fn error_category(err: &QueryError) -> ErrorCategory {
+    match err {
+        QueryError::RequestTimeout(_)
+        | QueryError::TimeoutError
+        | QueryError::DbError(DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. }, _) => {
+            ErrorCategory::Timeout
+        }
+        QueryError::DbError(DbError::Overloaded, _) => ErrorCategory::Overloaded,
+        QueryError::DbError(DbError::Unavailable { .. } | DbError::IsBootstrapping, _) => {
+            ErrorCategory::Unavailable
+        }
+        QueryError::DbError(DbError::ReadFailure { .. }, _) => ErrorCategory::ReadFailure,
+        QueryError::DbError(DbError::WriteFailure { .. }, _) => ErrorCategory::WriteFailure,
+        QueryError::BadQuery(_)
+        | QueryError::DbError(
+            DbError::SyntaxError
+            | DbError::Invalid
+            | DbError::AlreadyExists { .. }
+            | DbError::Unauthorized
+            | DbError::AuthenticationError
+            | DbError::Unprepared { .. },
+            _,
+        ) => ErrorCategory::InvalidRequest,
+        QueryError::IoError(_)
+        | QueryError::UnableToAllocStreamId
+        | QueryError::TooManyOrphanedStreamIds(_) => ErrorCategory::Connection,
+        _ => ErrorCategory::Other,
+    }
+}
+
+/// Executes the request and retries it on transient errors according to the retry policy.
+/// Non-idempotent requests are retried only if allowed explicitly by the retry configuration.
+/// All attempts are recorded as a single request, with the response time covering all attempts
+/// and the delays between them, because that's what the application would experience.
+async fn execute_with_retries<F, R>(
+    stats: &TryLock<SessionStats>,
+    retry: &RetryConf,
+    idempotent: bool,
+    statement_key: &str,
+    request: F,
+) -> Result<QueryResult, QueryError>
+where
+    F: Fn() -> R,
+    R: Future<Output = Result<QueryResult, QueryError>>,
+{
+    let start_time = stats.try_lock().unwrap().start_request();
+    let mut attempts = 1;
+    let mut rs = request().await;
+    while let Err(e) = &rs {
+        let retry_allowed =
+            error_category(e).is_transient() && (idempotent || retry.retry_non_idempotent);
+        match retry.delay(attempts) {
+            Some(delay) if retry_allowed => tokio::time::sleep(delay).await,
+            _ => break,
         }
+        attempts += 1;
+        rs = request().await;
+    }
+    let duration = Instant::now() - start_time;
+    stats
+        .try_lock()
+        .unwrap()
+        .complete_request(statement_key, duration, attempts, &rs);
+    rs
+}
+
+/// Records the request in the session stats if it was executed with tracing enabled and
+/// took at least the tracing threshold.
+/// The driver returns the tracing id only for successful requests, so failed ones are skipped.
+fn record_trace(
+    stats: &TryLock<SessionStats>,
+    tracing: &TracingConf,
+    rs: &Result<QueryResult, QueryError>,
+    cql: impl FnOnce() -> String,
+    duration: std::time::Duration,
+) {
+    if let Ok(QueryResult {
+        tracing_id: Some(tracing_id),
+        ..
+    }) = rs
+    {
+        if tracing.threshold.map(|t| duration >= t).unwrap_or(true) {
+            let request = TracedRequest {
+                tracing_id: *tracing_id,
+                cql: cql(),
+                duration,
+            };
+            let max_count = tracing.count.max(1);
+            stats.try_lock().unwrap().record_trace(request, max_count);
+        }
+    }
+}
+
+/// Counts the requests that triggered speculative execution.
+/// Attached as a history listener to the statements executed by the context.
+#[derive(Debug, Default)]
+struct SpeculativeExecutionCounter {
+    next_query_id: AtomicUsize,
+    speculative_queries: Mutex<HashSet<usize>>,
+    speculative_count: AtomicU64,
+}
+
+impl SpeculativeExecutionCounter {
+    /// Returns the number of requests that triggered speculative execution
+    /// since the last call and resets the counter.
+    fn take(&self) -> u64 {
+        self.speculative_count.swap(0, Ordering::Relaxed)
+    }
+
+    fn complete(&self, query_id: QueryId) {
+        if self.speculative_queries.lock().unwrap().remove(&query_id.0) {
+            self.speculative_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl HistoryListener for SpeculativeExecutionCounter {
+    fn log_query_start(&self) -> QueryId {
+        QueryId(self.next_query_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn log_query_success(&self, query_id: QueryId) {
+        self.complete(query_id)
+    }
+
+    fn log_query_error(&self, query_id: QueryId, _error: &QueryError) {
+        self.complete(query_id)
+    }
+
+    fn log_new_speculative_fiber(&self, query_id: QueryId) -> SpeculativeId {
+        let mut queries = self.speculative_queries.lock().unwrap();
+        queries.insert(query_id.0);
+        SpeculativeId(0)
+    }
+
+    fn log_attempt_start(
+        &self,
+        _query_id: QueryId,
+        _speculative_id: Option<SpeculativeId>,
+        _node_addr: SocketAddr,
+    ) -> AttemptId {
+        AttemptId(0)
+    }
+
+    fn log_attempt_success(&self, _attempt_id: AttemptId) {}
+
+    fn log_attempt_error(
+        &self,
+        _attempt_id: AttemptId,
+        _error: &QueryError,
+        _retry_decision: &RetryDecision,
+    ) {
     }
 }
 
@@ -249,7 +696,13 @@ impl Default for SessionStats {
 pub struct Context {
     session: Arc<scylla::Session>,
     statements: HashMap<String, Arc<PreparedStatement>>,
-    stats: TryLock<SessionStats>,
+    statement_options: HashMap<String, StatementOptions>,
+    profiles: HashMap<String, ExecutionProfileHandle>,
+    retry: RetryConf,
+    speculative_retries: usize,
+    speculative_counter: Option<Arc<SpeculativeExecutionCounter>>,
+    tracing: TracingConf,
+    stats: Arc<TryLock<SessionStats>>,
     #[rune(get, set, add_assign, copy)]
     pub load_cycle_count: u64,
     #[rune(get)]
@@ -266,16 +719,29 @@ unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
 impl Context {
-    pub fn new(session: scylla::Session) -> Context {
+    pub fn new(session: scylla::Session, conf: &ConnectionConf) -> Context {
+        let speculative =
+            conf.speculative.is_some() || conf.profiles.iter().any(|p| p.speculative.is_some());
         Context {
             session: Arc::new(session),
             statements: HashMap::new(),
-            stats: TryLock::new(SessionStats::new()),
+            statement_options: HashMap::new(),
+            profiles: execution_profiles(conf),
+            retry: conf.retry.clone(),
+            speculative_retries: conf.speculative_retries,
+            speculative_counter: speculative.then(Default::default),
+            tracing: TracingConf::default(),
+            stats: Arc::new(TryLock::new(SessionStats::new())),
             load_cycle_count: 0,
             data: Value::Object(Shared::new(Object::new())),
         }
     }
 
+    /// Enables CQL tracing of a sample of requests executed by prepared statements.
+    pub fn set_tracing(&mut self, tracing: TracingConf) {
+        self.tracing = tracing;
+    }
+
     /// Clones the context for use by another thread.
     /// The new clone gets fresh statistics.
     /// The user data gets passed through serialization and deserialization to avoid
@@ -283,10 +749,34 @@ impl Context {
     pub fn clone(&self) -> Result<Self, LatteError> {
         let serialized = rmp_serde::to_vec(&self.data)?;
         let deserialized: Value = rmp_serde::from_slice(&serialized)?;
+        // Each clone counts speculative executions of its own requests,
+        // so the statements must get their own history listener:
+        let speculative_counter: Option<Arc<SpeculativeExecutionCounter>> = self
+            .speculative_counter
+            .as_ref()
+            .map(|_| Default::default());
+        let statements = match &speculative_counter {
+            Some(counter) => self
+                .statements
+                .iter()
+                .map(|(key, statement)| {
+                    let mut statement = statement.as_ref().clone();
+                    statement.set_history_listener(counter.clone());
+                    (key.clone(), Arc::new(statement))
+                })
+                .collect(),
+            None => self.statements.clone(),
+        };
         Ok(Context {
             session: self.session.clone(),
-            statements: self.statements.clone(),
-            stats: TryLock::new(SessionStats::default()),
+            statements,
+            statement_options: self.statement_options.clone(),
+            profiles: self.profiles.clone(),
+            retry: self.retry.clone(),
+            speculative_retries: self.speculative_retries,
+            speculative_counter,
+            tracing: self.tracing.clone(),
+            stats: Arc::new(TryLock::new(SessionStats::default())),
             load_cycle_count: self.load_cycle_count,
             data: deserialized,
         })
@@ -315,49 +805,298 @@ impl Context {
 
     /// Prepares a statement and stores it in an internal statement map for future use.
     pub async fn prepare(&mut self, key: &str, cql: &str) -> Result<(), CassError> {
-        let statement = self
+        self.prepare_statement(key, cql, StatementOptions::default())
+            .await
+    }
+
+    /// Prepares a statement with custom execution options and stores it in an internal
+    /// statement map for future use. The options are given as an object with any of the
+    /// following fields: `profile`, `consistency`, `serial_consistency`, `timeout`, `idempotent`,
+    /// `page_size`, `timestamp`, `tracing`. Options set explicitly take precedence
+    /// over the options of the execution profile.
+    pub async fn prepare_with_options(
+        &mut self,
+        key: &str,
+        cql: &str,
+        options: Value,
+    ) -> Result<(), CassError> {
+        let options = options::from_rune_value(&options)?;
+        self.prepare_statement(key, cql, options).await
+    }
+
+    async fn prepare_statement(
+        &mut self,
+        key: &str,
+        cql: &str,
+        options: StatementOptions,
+    ) -> Result<(), CassError> {
+        let mut statement = self
             .session
             .prepare(cql)
             .await
             .map_err(|e| CassError::prepare_error(cql, e))?;
+        if let Some(profile) = &options.profile {
+            statement.set_execution_profile_handle(Some(self.profile(profile)?.clone()));
+        }
+        if let Some(speculative) = options.speculative {
+            let max_retry_count = options
+                .speculative_retries
+                .unwrap_or(self.speculative_retries);
+            let policy = speculative_execution_policy(speculative, max_retry_count);
+            let profile = statement
+                .get_execution_profile_handle()
+                .unwrap_or(self.session.get_default_execution_profile_handle())
+                .pointee_to_builder()
+                .speculative_execution_policy(Some(policy))
+                .build();
+            statement.set_execution_profile_handle(Some(profile.into_handle()));
+            if self.speculative_counter.is_none() {
+                self.speculative_counter = Some(Default::default());
+            }
+        }
+        if let Some(counter) = &self.speculative_counter {
+            statement.set_history_listener(counter.clone());
+        }
+        options::apply(&options, &mut statement);
         self.statements.insert(key.to_string(), Arc::new(statement));
+        self.statement_options.insert(key.to_string(), options);
         Ok(())
     }
 
+    /// Returns the CQL text and execution options of all prepared statements.
+    pub fn statement_confs(&self) -> BTreeMap<String, StatementConf> {
+        self.statements
+            .iter()
+            .map(|(key, statement)| {
+                let conf = StatementConf {
+                    cql: statement.get_statement().to_string(),
+                    options: self.statement_options.get(key).cloned().unwrap_or_default(),
+                };
+                (key.clone(), conf)
+            })
+            .collect()
+    }
+
     /// Executes an ad-hoc CQL statement with no parameters. Does not prepare.
     pub async fn execute(&self, cql: &str) -> Result<(), CassError> {
-        let start_time = self.stats.try_lock().unwrap().start_request();
-        let rs = self.session.query(cql, ()).await;
-        let duration = Instant::now() - start_time;
-        self.stats
-            .try_lock()
-            .unwrap()
-            .complete_request(duration, &rs);
-        rs.map_err(|e| CassError::query_execution_error(cql, &[], e))?;
+        self.query(cql).await?;
         Ok(())
     }
 
+    /// Executes an ad-hoc CQL statement with no parameters and returns the result rows.
+    /// Each row is returned as an object with fields named after the result columns.
+    /// Does not prepare.
+    pub async fn execute_with_result(&self, cql: &str) -> Result<Value, CassError> {
+        let rs = self.query(cql).await?;
+        rows::to_rune_rows(rs)
+    }
+
+    async fn query(&self, cql: &str) -> Result<QueryResult, CassError> {
+        let mut query = Query::new(cql);
+        if let Some(counter) = &self.speculative_counter {
+            query.set_history_listener(counter.clone());
+        }
+        let rs = execute_with_retries(
+            &self.stats,
+            &self.retry,
+            false,
+            AD_HOC_STATEMENT_KEY,
+            || self.session.query(query.clone(), ()),
+        )
+        .await;
+        rs.map_err(|e| CassError::query_execution_error(cql, &[], e))
+    }
+
     /// Executes a statement prepared and registered earlier by a call to `prepare`.
     pub async fn execute_prepared(&self, key: &str, params: Value) -> Result<(), CassError> {
-        let statement = self
-            .statements
+        self.query_prepared(key, params).await?;
+        Ok(())
+    }
+
+    /// Executes a statement prepared and registered earlier by a call to `prepare`
+    /// and returns the result rows.
+    /// Each row is returned as an object with fields named after the result columns.
+    pub async fn execute_prepared_with_result(
+        &self,
+        key: &str,
+        params: Value,
+    ) -> Result<Value, CassError> {
+        let rs = self.query_prepared(key, params).await?;
+        rows::to_rune_rows(rs)
+    }
+
+    /// Executes a conditional statement (lightweight transaction) prepared and registered earlier
+    /// by a call to `prepare` and returns true if the statement was applied.
+    /// Returns false if the condition was not met, e.g. the row in `INSERT ... IF NOT EXISTS`
+    /// already existed.
+    /// Returns an error if the statement is not conditional.
+    pub async fn execute_conditional(&self, key: &str, params: Value) -> Result<bool, CassError> {
+        let rs = self.query_prepared(key, params).await?;
+        lwt_applied(&rs).ok_or_else(|| CassError(CassErrorKind::NotConditional(key.to_string())))
+    }
+
+    /// Executes a statement prepared and registered earlier by a call to `prepare`
+    /// using the named execution profile instead of the profile the statement was prepared with.
+    pub async fn execute_prepared_with_profile(
+        &self,
+        key: &str,
+        profile: &str,
+        params: Value,
+    ) -> Result<(), CassError> {
+        let mut statement = self.statement(key)?.as_ref().clone();
+        statement.set_execution_profile_handle(Some(self.profile(profile)?.clone()));
+        self.query_statement(key, &statement, params).await?;
+        Ok(())
+    }
+
+    fn statement(&self, key: &str) -> Result<&Arc<PreparedStatement>, CassError> {
+        self.statements
             .get(key)
-            .ok_or_else(|| CassError(CassErrorKind::PreparedStatementNotFound(key.to_string())))?;
-        let params = bind::to_scylla_query_params(&params)?;
-        let start_time = self.stats.try_lock().unwrap().start_request();
-        let rs = self.session.execute(statement, params.clone()).await;
-        let duration = Instant::now() - start_time;
-        self.stats
-            .try_lock()
-            .unwrap()
-            .complete_request(duration, &rs);
-        rs.map_err(|e| CassError::query_execution_error(statement.get_statement(), &params, e))?;
+            .ok_or_else(|| CassError(CassErrorKind::PreparedStatementNotFound(key.to_string())))
+    }
+
+    fn profile(&self, name: &str) -> Result<&ExecutionProfileHandle, CassError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| CassError(CassErrorKind::ExecutionProfileNotFound(name.to_string())))
+    }
+
+    async fn query_prepared(&self, key: &str, params: Value) -> Result<QueryResult, CassError> {
+        self.query_statement(key, self.statement(key)?, params)
+            .await
+    }
+
+    async fn query_statement(
+        &self,
+        key: &str,
+        statement: &PreparedStatement,
+        params: Value,
+    ) -> Result<QueryResult, CassError> {
+        let params = bind::to_scylla_query_params(&params, variable_types(statement))?;
+        let idempotent = statement.get_is_idempotent();
+        let traced_statement;
+        let statement = if self.tracing.sample_next() {
+            traced_statement = {
+                let mut s = statement.clone();
+                s.set_tracing(true);
+                s
+            };
+            &traced_statement
+        } else {
+            statement
+        };
+        let start = Instant::now();
+        let rs = execute_with_retries(&self.stats, &self.retry, idempotent, key, || {
+            self.session.execute(statement, &params)
+        })
+        .await;
+        let cql = || statement.get_statement().to_string();
+        record_trace(&self.stats, &self.tracing, &rs, cql, Instant::now() - start);
+        rs.map_err(|e| CassError::query_execution_error(statement.get_statement(), &params, e))
+    }
+
+    /// Creates an empty batch of given type.
+    /// Valid batch types are `logged`, `unlogged` and `counter`.
+    pub fn batch(&self, batch_type: &str) -> Result<Batch, CassError> {
+        let batch_type = match batch_type.to_lowercase().as_str() {
+            "logged" => BatchType::Logged,
+            "unlogged" => BatchType::Unlogged,
+            "counter" => BatchType::Counter,
+            other => {
+                return Err(CassError(CassErrorKind::UnsupportedBatchType(
+                    other.to_string(),
+                )))
+            }
+        };
+        Ok(Batch {
+            batch_type,
+            statements: Vec::new(),
+        })
+    }
+
+    /// Executes all statements added to the batch as a single CQL batch request.
+    pub async fn execute_batch(&self, batch: &Batch) -> Result<(), CassError> {
+        let mut scylla_batch = scylla::batch::Batch::new(batch.batch_type);
+        let mut values = Vec::with_capacity(batch.statements.len());
+        for (key, params) in batch.statements.iter() {
+            let statement = self.statement(key)?;
+            scylla_batch.append_statement(statement.as_ref().clone());
+            values.push(bind::to_scylla_query_params(
+                params,
+                variable_types(statement),
+            )?);
+        }
+        // A batch can be safely retried only if all of its statements can be:
+        let idempotent = scylla_batch.statements.iter().all(|s| match s {
+            BatchStatement::PreparedStatement(s) => s.get_is_idempotent(),
+            BatchStatement::Query(s) => s.get_is_idempotent(),
+        });
+        scylla_batch.set_is_idempotent(idempotent);
+        if let Some(counter) = &self.speculative_counter {
+            scylla_batch.set_history_listener(counter.clone());
+        }
+        scylla_batch.set_tracing(self.tracing.sample_next());
+        let start = Instant::now();
+        let rs = execute_with_retries(
+            &self.stats,
+            &self.retry,
+            idempotent,
+            BATCH_STATEMENT_KEY,
+            || self.session.batch(&scylla_batch, &values),
+        )
+        .await;
+        self.stats.try_lock().unwrap().complete_batch(values.len());
+        let cql = || {
+            batch
+                .statements
+                .iter()
+                .filter_map(|(key, _)| self.statements.get(key))
+                .map(|s| s.get_statement())
+                .join("; ")
+        };
+        record_trace(&self.stats, &self.tracing, &rs, cql, Instant::now() - start);
+        rs.map_err(|e| CassError::query_execution_error(&cql(), &values.concat(), e))?;
         Ok(())
     }
 
+    /// Creates a pager that fetches the results of a prepared statement page by page.
+    /// Nothing is sent to the server until the first page is requested.
+    /// Each page fetch is recorded as a separate request.
+    pub fn pager(&self, key: &str, params: Value, page_size: i64) -> Result<Pager, CassError> {
+        let mut statement = self.statement(key)?.as_ref().clone();
+        statement.set_page_size(page_size.clamp(1, i32::MAX as i64) as i32);
+        let params = bind::to_scylla_query_params(&params, variable_types(&statement))?;
+        Ok(Pager {
+            session: self.session.clone(),
+            key: key.to_string(),
+            statement,
+            params,
+            paging_state: None,
+            finished: false,
+            stats: self.stats.clone(),
+            retry: self.retry.clone(),
+            tracing: self.tracing.clone(),
+        })
+    }
+
+    /// Fetches the server-side trace of a request from `system_traces`.
+    pub async fn fetch_trace(&self, request: &TracedRequest) -> Result<RequestTrace, CassError> {
+        let info = self
+            .session
+            .get_tracing_info(&request.tracing_id)
+            .await
+            .map_err(|e| {
+                CassError::query_execution_error("SELECT ... FROM system_traces.events", &[], e)
+            })?;
+        Ok(RequestTrace::new(request, info))
+    }
+
     /// Returns the current accumulated request stats snapshot and resets the stats.
     pub fn take_session_stats(&self) -> SessionStats {
         let mut stats = self.stats.try_lock().unwrap();
+        if let Some(counter) = &self.speculative_counter {
+            stats.speculative_count += counter.take();
+        }
         let result = stats.clone();
         stats.reset();
         result
@@ -365,18 +1104,122 @@ impl Context {
 
     /// Resets query and request counters
     pub fn reset_session_stats(&self) {
+        if let Some(counter) = &self.speculative_counter {
+            counter.take();
+        }
         self.stats.try_lock().unwrap().reset();
     }
 }
 
+/// A list of prepared statements with their parameters, to be executed together as a single
+/// CQL batch. Created by `Context::batch` and executed by `Context::execute_batch`.
+#[derive(Any)]
+pub struct Batch {
+    batch_type: BatchType,
+    statements: Vec<(String, Value)>,
+}
+
+impl Batch {
+    /// Adds a statement prepared and registered earlier by a call to `Context::prepare`
+    /// to the batch. The parameters are bound when the batch gets executed.
+    pub fn add(&mut self, key: &str, params: Value) {
+        self.statements.push((key.to_string(), params));
+    }
+
+    /// Returns the number of statements in the batch.
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Returns true if no statements have been added to the batch.
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+/// Iterates over the pages of the result of a prepared statement.
+/// Created by `Context::pager`.
+#[derive(Any)]
+pub struct Pager {
+    session: Arc<scylla::Session>,
+    key: String,
+    statement: PreparedStatement,
+    params: Vec<CqlValue>,
+    paging_state: Option<Bytes>,
+    finished: bool,
+    stats: Arc<TryLock<SessionStats>>,
+    retry: RetryConf,
+    tracing: TracingConf,
+}
+
+impl Pager {
+    /// Fetches the next page of results.
+    /// Returns the rows of the page or `None` if there are no more pages.
+    pub async fn next(&mut self) -> Result<Option<Value>, CassError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let idempotent = self.statement.get_is_idempotent();
+        let traced_statement;
+        let statement = if self.tracing.sample_next() {
+            traced_statement = {
+                let mut s = self.statement.clone();
+                s.set_tracing(true);
+                s
+            };
+            &traced_statement
+        } else {
+            &self.statement
+        };
+        let start = Instant::now();
+        let rs = execute_with_retries(&self.stats, &self.retry, idempotent, &self.key, || {
+            self.session
+                .execute_paged(statement, &self.params, self.paging_state.clone())
+        })
+        .await;
+        let cql = || self.statement.get_statement().to_string();
+        record_trace(&self.stats, &self.tracing, &rs, cql, Instant::now() - start);
+        let rs = rs.map_err(|e| {
+            CassError::query_execution_error(self.statement.get_statement(), &self.params, e)
+        })?;
+        self.paging_state = rs.paging_state.clone();
+        self.finished = self.paging_state.is_none();
+        Ok(Some(rows::to_rune_rows(rs)?))
+    }
+
+    /// Returns true if there might be more pages to fetch.
+    pub fn has_more_pages(&self) -> bool {
+        !self.finished
+    }
+
+    /// Returns the paging state the next page will be fetched from.
+    /// Can be used to resume the iteration later with `set_paging_state`.
+    pub fn paging_state(&self) -> Option<rune::runtime::Bytes> {
+        self.paging_state
+            .as_ref()
+            .map(|s| rune::runtime::Bytes::from_vec(s.to_vec()))
+    }
+
+    /// Sets the paging state the next page will be fetched from.
+    pub fn set_paging_state(&mut self, state: rune::runtime::Bytes) {
+        self.paging_state = Some(Bytes::from(state.into_vec()));
+        self.finished = false;
+    }
+}
+
+/// Returns the names and types of the bind markers of the statement.
+fn variable_types(statement: &PreparedStatement) -> &[ColumnSpec] {
+    &statement.get_prepared_metadata().col_specs
+}
+
 /// Functions for binding rune values to CQL parameters
 mod bind {
     use crate::CassErrorKind;
-    use scylla::frame::response::result::CqlValue;
+    use scylla::frame::response::result::{ColumnSpec, ColumnType, CqlValue};
 
     use super::*;
 
-    fn to_scylla_value(v: &Value) -> Result<CqlValue, CassError> {
+    pub fn to_scylla_value(v: &Value) -> Result<CqlValue, CassError> {
         match v {
             Value::Bool(v) => Ok(CqlValue::Boolean(*v)),
             Value::Byte(v) => Ok(CqlValue::TinyInt(*v as i8)),
@@ -394,6 +1237,17 @@ mod bind {
                 let elements = v.as_ref().iter().map(to_scylla_value).try_collect()?;
                 Ok(CqlValue::List(elements))
             }
+            Value::Tuple(v) => {
+                let v = v.borrow_ref().unwrap();
+                let elements = v
+                    .iter()
+                    .map(|v| match to_scylla_value(v)? {
+                        CqlValue::Empty => Ok::<_, CassError>(None),
+                        v => Ok(Some(v)),
+                    })
+                    .try_collect()?;
+                Ok(CqlValue::Tuple(elements))
+            }
             Value::Object(v) => {
                 let borrowed = v.borrow_ref().unwrap();
 
@@ -440,6 +1294,42 @@ mod bind {
                 } else if h == Int8::type_hash() {
                     let int8: &Int8 = obj.downcast_borrow_ref().unwrap();
                     Ok(CqlValue::TinyInt(int8.0))
+                } else if h == Float32::type_hash() {
+                    let float32: &Float32 = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Float(float32.0))
+                } else if h == Date::type_hash() {
+                    let date: &Date = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Date(date.0))
+                } else if h == Time::type_hash() {
+                    let time: &Time = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Time(time.0))
+                } else if h == Timestamp::type_hash() {
+                    let timestamp: &Timestamp = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Timestamp(timestamp.0))
+                } else if h == Duration::type_hash() {
+                    let duration: &Duration = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Duration(duration.0))
+                } else if h == Decimal::type_hash() {
+                    let decimal: &Decimal = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Decimal(decimal.0.clone()))
+                } else if h == Varint::type_hash() {
+                    let varint: &Varint = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Varint(varint.0.clone()))
+                } else if h == Inet::type_hash() {
+                    let inet: &Inet = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Inet(inet.0))
+                } else if h == Timeuuid::type_hash() {
+                    let timeuuid: &Timeuuid = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Timeuuid(timeuuid.0))
+                } else if h == Counter::type_hash() {
+                    let counter: &Counter = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Counter(scylla::frame::value::Counter(counter.0)))
+                } else if h == Map::type_hash() {
+                    let map: &Map = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Map(map.0.clone()))
+                } else if h == Set::type_hash() {
+                    let set: &Set = obj.downcast_borrow_ref().unwrap();
+                    Ok(CqlValue::Set(set.0.clone()))
                 } else {
                     Err(CassError(CassErrorKind::UnsupportedType(
                         v.type_info().unwrap(),
@@ -454,7 +1344,12 @@ mod bind {
 
     /// Binds parameters passed as a single rune value to the arguments of the statement.
     /// The `params` value can be a tuple, a vector, a struct or an object.
-    pub fn to_scylla_query_params(params: &Value) -> Result<Vec<CqlValue>, CassError> {
+    /// Values are converted to the types of the bound variables declared in the prepared statement
+    /// metadata, so e.g. a Rune integer can be bound to an `int` column without calling `to_i32()`.
+    pub fn to_scylla_query_params(
+        params: &Value,
+        types: &[ColumnSpec],
+    ) -> Result<Vec<CqlValue>, CassError> {
         let mut values = Vec::new();
         match params {
             Value::Tuple(tuple) => {
@@ -469,13 +1364,520 @@ mod bind {
                     values.push(to_scylla_value(v)?);
                 }
             }
+            Value::Object(object) => {
+                let object = object.borrow_ref().unwrap();
+                values = to_named_values(&object, types)?;
+            }
+            Value::Struct(s) => {
+                let s = s.borrow_ref().unwrap();
+                values = to_named_values(s.data(), types)?;
+            }
             other => {
                 return Err(CassError(CassErrorKind::UnsupportedType(
                     other.type_info().unwrap(),
                 )));
             }
         }
-        Ok(values)
+        if values.len() != types.len() {
+            return Err(CassError(CassErrorKind::InvalidQueryParamsCount(
+                types.len(),
+                values.len(),
+            )));
+        }
+        values
+            .into_iter()
+            .zip(types)
+            .map(|(v, spec)| {
+                convert(v, &spec.typ).map_err(|msg| {
+                    CassError(CassErrorKind::InvalidQueryParam(spec.name.clone(), msg))
+                })
+            })
+            .collect()
+    }
+
+    /// Looks up the values of the bind markers by their names in the fields of an object.
+    /// Every bind marker must have a matching field and every field must match a bind marker.
+    fn to_named_values(object: &Object, types: &[ColumnSpec]) -> Result<Vec<CqlValue>, CassError> {
+        if let Some(name) = object
+            .keys()
+            .find(|name| !types.iter().any(|spec| spec.name == name.as_str()))
+        {
+            return Err(CassError(CassErrorKind::InvalidQueryParam(
+                name.to_string(),
+                "no bind marker with this name in the statement".to_string(),
+            )));
+        }
+        types
+            .iter()
+            .map(|spec| match object.get(spec.name.as_str()) {
+                Some(v) => to_scylla_value(v),
+                None => Err(CassError(CassErrorKind::InvalidQueryParam(
+                    spec.name.clone(),
+                    "missing value".to_string(),
+                ))),
+            })
+            .collect()
+    }
+
+    /// Converts a value to the given CQL type.
+    /// Returns an error message if the value cannot be represented as the target type.
+    pub fn convert(v: CqlValue, typ: &ColumnType) -> Result<CqlValue, String> {
+        let mismatch = |v: &CqlValue| format!("cannot convert {v:?} to {}", type_name(typ));
+        if let Some(i) = as_i64(&v) {
+            let out_of_range = || format!("value {i} out of range for {}", type_name(typ));
+            return match typ {
+                ColumnType::BigInt => Ok(CqlValue::BigInt(i)),
+                ColumnType::Int => Ok(CqlValue::Int(i.try_into().map_err(|_| out_of_range())?)),
+                ColumnType::SmallInt => Ok(CqlValue::SmallInt(
+                    i.try_into().map_err(|_| out_of_range())?,
+                )),
+                ColumnType::TinyInt => {
+                    Ok(CqlValue::TinyInt(i.try_into().map_err(|_| out_of_range())?))
+                }
+                ColumnType::Counter => Ok(CqlValue::Counter(scylla::frame::value::Counter(i))),
+                ColumnType::Timestamp => Ok(CqlValue::Timestamp(CqlTimestamp(i))),
+                ColumnType::Varint => Ok(CqlValue::Varint(CqlVarint::from_signed_bytes_be(
+                    numbers::to_signed_bytes_be(i as i128),
+                ))),
+                ColumnType::Decimal => Ok(CqlValue::Decimal(
+                    CqlDecimal::from_signed_be_bytes_and_exponent(
+                        numbers::to_signed_bytes_be(i as i128),
+                        0,
+                    ),
+                )),
+                ColumnType::Double => Ok(CqlValue::Double(i as f64)),
+                ColumnType::Float => Ok(CqlValue::Float(i as f32)),
+                ColumnType::Custom(_) => Ok(v),
+                _ => Err(mismatch(&v)),
+            };
+        }
+        match (v, typ) {
+            (CqlValue::Empty, _) => Ok(CqlValue::Empty),
+            (v, ColumnType::Custom(_)) => Ok(v),
+            (CqlValue::Double(f), ColumnType::Float) => Ok(CqlValue::Float(f as f32)),
+            (CqlValue::Float(f), ColumnType::Double) => Ok(CqlValue::Double(f as f64)),
+            (CqlValue::Text(s), ColumnType::Text) => Ok(CqlValue::Text(s)),
+            (CqlValue::Text(s), ColumnType::Ascii) if s.is_ascii() => Ok(CqlValue::Ascii(s)),
+            (CqlValue::Text(s), _) if is_parsed_from_text(typ) => parse(&s, typ)
+                .map_err(|e| format!("cannot convert {s:?} to {}: {e}", type_name(typ))),
+            (CqlValue::List(v) | CqlValue::Set(v), ColumnType::List(t)) => {
+                Ok(CqlValue::List(convert_all(v, t)?))
+            }
+            (CqlValue::List(v) | CqlValue::Set(v), ColumnType::Set(t)) => {
+                Ok(CqlValue::Set(convert_all(v, t)?))
+            }
+            (CqlValue::List(v), ColumnType::Map(kt, vt)) => {
+                let mut entries = Vec::with_capacity(v.len());
+                for entry in v {
+                    match entry {
+                        CqlValue::Tuple(kv) if kv.len() == 2 => {
+                            let mut kv = kv.into_iter();
+                            let k = kv.next().unwrap().unwrap_or(CqlValue::Empty);
+                            let v = kv.next().unwrap().unwrap_or(CqlValue::Empty);
+                            entries.push((convert(k, kt)?, convert(v, vt)?));
+                        }
+                        other => {
+                            return Err(format!("expected a (key, value) tuple, got {other:?}"))
+                        }
+                    }
+                }
+                Ok(CqlValue::Map(entries))
+            }
+            (CqlValue::Map(v), ColumnType::Map(kt, vt)) => {
+                let entries = v
+                    .into_iter()
+                    .map(|(k, v)| Ok((convert(k, kt)?, convert(v, vt)?)))
+                    .collect::<Result<_, String>>()?;
+                Ok(CqlValue::Map(entries))
+            }
+            (CqlValue::UserDefinedType { fields, .. }, ColumnType::Map(kt, vt)) => {
+                let entries = fields
+                    .into_iter()
+                    .map(|(k, v)| {
+                        let v = v.unwrap_or(CqlValue::Empty);
+                        Ok((convert(CqlValue::Text(k), kt)?, convert(v, vt)?))
+                    })
+                    .collect::<Result<_, String>>()?;
+                Ok(CqlValue::Map(entries))
+            }
+            (CqlValue::Tuple(v), ColumnType::Tuple(types)) => {
+                if v.len() != types.len() {
+                    return Err(format!(
+                        "expected a tuple of {} elements, got {}",
+                        types.len(),
+                        v.len()
+                    ));
+                }
+                let elements = v
+                    .into_iter()
+                    .zip(types)
+                    .map(|(v, t)| v.map(|v| convert(v, t)).transpose())
+                    .collect::<Result<_, String>>()?;
+                Ok(CqlValue::Tuple(elements))
+            }
+            (
+                CqlValue::UserDefinedType { mut fields, .. },
+                ColumnType::UserDefinedType {
+                    type_name,
+                    keyspace,
+                    field_types,
+                },
+            ) => {
+                // Fields must be sent in the order of the type definition:
+                let mut ordered = Vec::with_capacity(field_types.len());
+                for (name, t) in field_types {
+                    let value = match fields.iter().position(|(k, _)| k == name) {
+                        Some(pos) => fields.swap_remove(pos).1,
+                        None => None,
+                    };
+                    ordered.push((name.clone(), value.map(|v| convert(v, t)).transpose()?));
+                }
+                if let Some((name, _)) = fields.first() {
+                    return Err(format!(
+                        "unknown field {name} of type {keyspace}.{type_name}"
+                    ));
+                }
+                Ok(CqlValue::UserDefinedType {
+                    keyspace: keyspace.clone(),
+                    type_name: type_name.clone(),
+                    fields: ordered,
+                })
+            }
+            (v, typ) if has_type(&v, typ) => Ok(v),
+            (v, _) => Err(mismatch(&v)),
+        }
+    }
+
+    fn convert_all(values: Vec<CqlValue>, typ: &ColumnType) -> Result<Vec<CqlValue>, String> {
+        values.into_iter().map(|v| convert(v, typ)).collect()
+    }
+
+    fn as_i64(v: &CqlValue) -> Option<i64> {
+        match v {
+            CqlValue::BigInt(i) => Some(*i),
+            CqlValue::Int(i) => Some(*i as i64),
+            CqlValue::SmallInt(i) => Some(*i as i64),
+            CqlValue::TinyInt(i) => Some(*i as i64),
+            _ => None,
+        }
+    }
+
+    fn is_parsed_from_text(typ: &ColumnType) -> bool {
+        matches!(
+            typ,
+            ColumnType::Uuid
+                | ColumnType::Timeuuid
+                | ColumnType::Inet
+                | ColumnType::Timestamp
+                | ColumnType::Date
+                | ColumnType::Time
+                | ColumnType::Decimal
+                | ColumnType::Varint
+        )
+    }
+
+    fn parse(s: &str, typ: &ColumnType) -> Result<CqlValue, String> {
+        let s = Value::from(s.to_string());
+        let value = match typ {
+            ColumnType::Uuid => CqlValue::Uuid(uuid_from_str(&s)?),
+            ColumnType::Timeuuid => CqlValue::Timeuuid(uuid_from_str(&s)?.into()),
+            ColumnType::Inet => CqlValue::Inet(
+                string_arg("inet", &s)
+                    .and_then(|s| inet(&s))
+                    .map_err(|e| e.to_string())?
+                    .0,
+            ),
+            ColumnType::Timestamp => {
+                CqlValue::Timestamp(timestamp(s).map_err(|e| e.to_string())?.0)
+            }
+            ColumnType::Date => CqlValue::Date(date(s).map_err(|e| e.to_string())?.0),
+            ColumnType::Time => CqlValue::Time(time(s).map_err(|e| e.to_string())?.0),
+            ColumnType::Decimal => CqlValue::Decimal(decimal(s).map_err(|e| e.to_string())?.0),
+            ColumnType::Varint => CqlValue::Varint(varint(s).map_err(|e| e.to_string())?.0),
+            _ => unreachable!(),
+        };
+        Ok(value)
+    }
+
+    fn uuid_from_str(s: &Value) -> Result<uuid::Uuid, String> {
+        let s = string_arg("uuid", s).map_err(|e| e.to_string())?;
+        uuid::Uuid::parse_str(&s).map_err(|e| e.to_string())
+    }
+
+    /// Returns true if the value is already of the given type.
+    fn has_type(v: &CqlValue, typ: &ColumnType) -> bool {
+        matches!(
+            (v, typ),
+            (CqlValue::Ascii(_), ColumnType::Ascii)
+                | (CqlValue::Boolean(_), ColumnType::Boolean)
+                | (CqlValue::Blob(_), ColumnType::Blob)
+                | (CqlValue::Counter(_), ColumnType::Counter)
+                | (CqlValue::Date(_), ColumnType::Date)
+                | (CqlValue::Decimal(_), ColumnType::Decimal)
+                | (CqlValue::Double(_), ColumnType::Double)
+                | (CqlValue::Duration(_), ColumnType::Duration)
+                | (CqlValue::Float(_), ColumnType::Float)
+                | (CqlValue::Timestamp(_), ColumnType::Timestamp)
+                | (CqlValue::Inet(_), ColumnType::Inet)
+                | (CqlValue::Time(_), ColumnType::Time)
+                | (CqlValue::Timeuuid(_), ColumnType::Timeuuid)
+                | (CqlValue::Uuid(_), ColumnType::Uuid)
+                | (CqlValue::Varint(_), ColumnType::Varint)
+        )
+    }
+
+    /// Returns the CQL name of the type, e.g. `map<int, text>`.
+    pub fn type_name(typ: &ColumnType) -> String {
+        match typ {
+            ColumnType::Custom(name) => format!("'{name}'"),
+            ColumnType::Ascii => "ascii".to_string(),
+            ColumnType::Boolean => "boolean".to_string(),
+            ColumnType::Blob => "blob".to_string(),
+            ColumnType::Counter => "counter".to_string(),
+            ColumnType::Date => "date".to_string(),
+            ColumnType::Decimal => "decimal".to_string(),
+            ColumnType::Double => "double".to_string(),
+            ColumnType::Duration => "duration".to_string(),
+            ColumnType::Float => "float".to_string(),
+            ColumnType::Int => "int".to_string(),
+            ColumnType::BigInt => "bigint".to_string(),
+            ColumnType::Text => "text".to_string(),
+            ColumnType::Timestamp => "timestamp".to_string(),
+            ColumnType::Inet => "inet".to_string(),
+            ColumnType::List(t) => format!("list<{}>", type_name(t)),
+            ColumnType::Map(k, v) => format!("map<{}, {}>", type_name(k), type_name(v)),
+            ColumnType::Set(t) => format!("set<{}>", type_name(t)),
+            ColumnType::UserDefinedType {
+                type_name,
+                keyspace,
+                ..
+            } => format!("{keyspace}.{type_name}"),
+            ColumnType::SmallInt => "smallint".to_string(),
+            ColumnType::TinyInt => "tinyint".to_string(),
+            ColumnType::Time => "time".to_string(),
+            ColumnType::Timeuuid => "timeuuid".to_string(),
+            ColumnType::Tuple(types) => {
+                format!("tuple<{}>", types.iter().map(type_name).join(", "))
+            }
+            ColumnType::Uuid => "uuid".to_string(),
+            ColumnType::Varint => "varint".to_string(),
+        }
+    }
+}
+
+/// Functions for reading statement execution options from rune values
+mod options {
+    use clap::ValueEnum;
+
+    use crate::config::{Consistency, SerialConsistency};
+    use crate::CassErrorKind;
+
+    use super::*;
+
+    fn invalid(key: &str, msg: impl ToString) -> CassError {
+        CassError(CassErrorKind::InvalidStatementOption(
+            key.to_string(),
+            msg.to_string(),
+        ))
+    }
+
+    fn as_string(key: &str, v: &Value) -> Result<String, CassError> {
+        match v {
+            Value::StaticString(v) => Ok(v.as_str().to_string()),
+            Value::String(v) => Ok(v.borrow_ref().unwrap().as_str().to_string()),
+            _ => Err(invalid(key, "expected a string")),
+        }
+    }
+
+    fn as_bool(key: &str, v: &Value) -> Result<bool, CassError> {
+        match v {
+            Value::Bool(v) => Ok(*v),
+            _ => Err(invalid(key, "expected a bool")),
+        }
+    }
+
+    fn as_integer(key: &str, v: &Value) -> Result<i64, CassError> {
+        match v {
+            Value::Integer(v) => Ok(*v),
+            _ => Err(invalid(key, "expected an integer")),
+        }
+    }
+
+    /// Reads statement options from a rune object.
+    /// Unknown fields are reported as errors, so typos don't go unnoticed.
+    pub fn from_rune_value(options: &Value) -> Result<StatementOptions, CassError> {
+        let options = match options {
+            Value::Object(options) => options.borrow_ref().unwrap(),
+            _ => return Err(invalid("", "expected an object")),
+        };
+        let mut result = StatementOptions::default();
+        for (key, v) in options.iter() {
+            let key = key.as_str();
+            match key {
+                "profile" => result.profile = Some(as_string(key, v)?),
+                "consistency" => {
+                    let c = as_string(key, v)?;
+                    result.consistency =
+                        Some(Consistency::from_str(&c, true).map_err(|e| invalid(key, e))?);
+                }
+                "serial_consistency" => {
+                    let c = as_string(key, v)?;
+                    result.serial_consistency =
+                        Some(SerialConsistency::from_str(&c, true).map_err(|e| invalid(key, e))?);
+                }
+                "timeout" => {
+                    let t = as_string(key, v)?;
+                    let t = parse_duration::parse(&t).map_err(|e| invalid(key, e))?;
+                    result.request_timeout = Some(t);
+                }
+                "idempotent" => result.idempotent = Some(as_bool(key, v)?),
+                "page_size" => {
+                    let p = as_integer(key, v)?;
+                    let p = i32::try_from(p)
+                        .ok()
+                        .filter(|p| *p > 0)
+                        .ok_or_else(|| invalid(key, "expected a positive 32-bit integer"))?;
+                    result.page_size = Some(p);
+                }
+                "timestamp" => result.timestamp = Some(as_integer(key, v)?),
+                "tracing" => result.tracing = Some(as_bool(key, v)?),
+                "speculative" => {
+                    let s = as_string(key, v)?;
+                    result.speculative = Some(s.parse().map_err(|e| invalid(key, e))?);
+                }
+                "speculative_retries" => {
+                    let r = as_integer(key, v)?;
+                    let r = usize::try_from(r).map_err(|e| invalid(key, e))?;
+                    result.speculative_retries = Some(r);
+                }
+                _ => return Err(invalid(key, "unknown option")),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Configures the prepared statement according to the options.
+    pub fn apply(options: &StatementOptions, statement: &mut PreparedStatement) {
+        if let Some(c) = options.consistency {
+            statement.set_consistency(c.scylla_consistency());
+        }
+        if let Some(c) = options.serial_consistency {
+            statement.set_serial_consistency(Some(c.scylla_serial_consistency()));
+        }
+        if let Some(t) = options.request_timeout {
+            statement.set_request_timeout(Some(t));
+        }
+        if let Some(i) = options.idempotent {
+            statement.set_is_idempotent(i);
+        }
+        if let Some(p) = options.page_size {
+            statement.set_page_size(p);
+        }
+        if let Some(t) = options.timestamp {
+            statement.set_timestamp(Some(t));
+        }
+        if let Some(t) = options.tracing {
+            statement.set_tracing(t);
+        }
+    }
+}
+
+/// Functions for converting CQL query results to rune values
+mod rows {
+    use scylla::frame::response::result::CqlValue;
+
+    use super::*;
+
+    /// Converts a single CQL value to a rune value.
+    /// Integers of all sizes become rune integers, sets and lists become vectors,
+    /// maps with text keys and UDTs become objects.
+    /// Dates, times, timestamps, durations, decimals, varints, inet addresses and uuids
+    /// become wrapper types which can be bound back to statement parameters.
+    /// Null values are converted to `None`.
+    fn to_rune_value(v: CqlValue) -> Result<Value, CassError> {
+        match v {
+            CqlValue::Boolean(v) => Ok(Value::Bool(v)),
+            CqlValue::TinyInt(v) => Ok(Value::Integer(v as i64)),
+            CqlValue::SmallInt(v) => Ok(Value::Integer(v as i64)),
+            CqlValue::Int(v) => Ok(Value::Integer(v as i64)),
+            CqlValue::BigInt(v) => Ok(Value::Integer(v)),
+            CqlValue::Counter(v) => Ok(Value::Integer(v.0)),
+            CqlValue::Timestamp(v) => Ok(Value::from(Timestamp(v))),
+            CqlValue::Float(v) => Ok(Value::Float(v as f64)),
+            CqlValue::Double(v) => Ok(Value::Float(v)),
+            CqlValue::Text(v) | CqlValue::Ascii(v) => Ok(Value::from(v)),
+            CqlValue::Inet(v) => Ok(Value::from(Inet(v))),
+            CqlValue::Blob(v) => Ok(Value::from(rune::runtime::Bytes::from_vec(v))),
+            CqlValue::Uuid(v) => Ok(Value::from(Uuid(v))),
+            CqlValue::Timeuuid(v) => Ok(Value::from(Timeuuid(v))),
+            CqlValue::Date(v) => Ok(Value::from(Date(v))),
+            CqlValue::Time(v) => Ok(Value::from(Time(v))),
+            CqlValue::Duration(v) => Ok(Value::from(Duration(v))),
+            CqlValue::Decimal(v) => Ok(Value::from(Decimal(v))),
+            CqlValue::Varint(v) => Ok(Value::from(Varint(v))),
+            CqlValue::Empty => Ok(Value::Option(Shared::new(None))),
+            CqlValue::List(v) | CqlValue::Set(v) => {
+                let elements = v.into_iter().map(to_rune_value).try_collect()?;
+                Ok(Value::vec(elements))
+            }
+            CqlValue::Tuple(v) => {
+                let elements = v.into_iter().map(to_rune_opt_value).try_collect()?;
+                Ok(Value::tuple(elements))
+            }
+            CqlValue::Map(v) => {
+                if v.iter().all(|(k, _)| k.as_text().is_some()) {
+                    let mut object = Object::with_capacity(v.len());
+                    for (k, v) in v {
+                        object.insert(k.into_string().unwrap(), to_rune_value(v)?);
+                    }
+                    Ok(Value::from(object))
+                } else {
+                    let mut entries = Vec::with_capacity(v.len());
+                    for (k, v) in v {
+                        entries.push(Value::tuple(vec![to_rune_value(k)?, to_rune_value(v)?]));
+                    }
+                    Ok(Value::vec(entries))
+                }
+            }
+            CqlValue::UserDefinedType {
+                keyspace,
+                type_name,
+                fields,
+            } => {
+                let mut object = Object::with_capacity(fields.len() + 2);
+                // Keep the type information, so the object can be bound back to a UDT parameter:
+                object.insert("_keyspace".to_string(), Value::from(keyspace));
+                object.insert("_type_name".to_string(), Value::from(type_name));
+                for (k, v) in fields {
+                    object.insert(k, to_rune_opt_value(v)?);
+                }
+                Ok(Value::from(object))
+            }
+        }
+    }
+
+    fn to_rune_opt_value(v: Option<CqlValue>) -> Result<Value, CassError> {
+        match v {
+            Some(v) => to_rune_value(v),
+            None => Ok(Value::Option(Shared::new(None))),
+        }
+    }
+
+    /// Converts the rows of the query result to a vector of objects.
+    /// Each object maps column names to column values.
+    /// Returns an empty vector if the query does not return rows.
+    pub fn to_rune_rows(rs: QueryResult) -> Result<Value, CassError> {
+        let col_names = rs.col_specs.iter().map(|c| c.name.clone()).collect_vec();
+        let rows = rs.rows.unwrap_or_default();
+        let mut result = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut object = Object::with_capacity(col_names.len());
+            for (name, value) in col_names.iter().zip(row.columns) {
+                object.insert(name.clone(), to_rune_opt_value(value)?);
+            }
+            result.push(Value::from(object));
+        }
+        Ok(Value::vec(result))
     }
 }
 
@@ -513,6 +1915,139 @@ pub struct Int16(pub i16);
 #[derive(Clone, Debug, Any)]
 pub struct Int32(pub i32);
 
+#[derive(Clone, Debug, Any)]
+pub struct Float32(pub f32);
+
+/// Cassandra `date`: number of days since the Unix epoch, shifted by 2^31
+#[derive(Clone, Debug, Any)]
+pub struct Date(pub CqlDate);
+
+impl Date {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        let days = self.0 .0 as i64 - (1 << 31);
+        match NaiveDate::default().checked_add_signed(chrono::Duration::days(days)) {
+            Some(date) => write!(buf, "{date}"),
+            None => write!(buf, "{days}"),
+        }
+    }
+}
+
+/// Cassandra `time`: number of nanoseconds since midnight
+#[derive(Clone, Debug, Any)]
+pub struct Time(pub CqlTime);
+
+impl Time {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        let nanos = self.0 .0;
+        let secs = (nanos / 1_000_000_000) as u32;
+        match NaiveTime::from_num_seconds_from_midnight_opt(secs, (nanos % 1_000_000_000) as u32) {
+            Some(time) => write!(buf, "{time}"),
+            None => write!(buf, "{nanos}"),
+        }
+    }
+}
+
+/// Cassandra `timestamp`: number of milliseconds since the Unix epoch
+#[derive(Clone, Debug, Any)]
+pub struct Timestamp(pub CqlTimestamp);
+
+impl Timestamp {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        match NaiveDateTime::from_timestamp_millis(self.0 .0) {
+            Some(ts) => write!(buf, "{}", ts.format("%Y-%m-%dT%H:%M:%S%.3fZ")),
+            None => write!(buf, "{}", self.0 .0),
+        }
+    }
+}
+
+/// Cassandra `duration`: months, days and nanoseconds
+#[derive(Clone, Debug, Any)]
+pub struct Duration(pub CqlDuration);
+
+impl Duration {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        let d = &self.0;
+        write!(buf, "{}mo{}d{}ns", d.months, d.days, d.nanoseconds)
+    }
+}
+
+#[derive(Clone, Debug, Any)]
+pub struct Decimal(pub CqlDecimal);
+
+impl Decimal {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        let (bytes, scale) = self.0.as_signed_be_bytes_slice_and_exponent();
+        match numbers::from_signed_bytes_be(bytes) {
+            Some(unscaled) => write!(buf, "{}", numbers::format_decimal(unscaled, scale)),
+            None => write!(buf, "<decimal of {} bytes>", bytes.len()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Any)]
+pub struct Varint(pub CqlVarint);
+
+impl Varint {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        let bytes = self.0.as_signed_bytes_be_slice();
+        match numbers::from_signed_bytes_be(bytes) {
+            Some(v) => write!(buf, "{v}"),
+            None => write!(buf, "<varint of {} bytes>", bytes.len()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Any)]
+pub struct Inet(pub IpAddr);
+
+impl Inet {
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        write!(buf, "{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug, Any)]
+pub struct Timeuuid(pub CqlTimeuuid);
+
+impl Timeuuid {
+    /// Returns a version 1 UUID derived deterministically from the hash of given integer.
+    /// The timestamp embedded in the UUID comes from the hash as well, so the generated
+    /// UUIDs are not ordered by time.
+    pub fn new(i: i64) -> Timeuuid {
+        let mut hash = MetroHash128::new();
+        i.hash(&mut hash);
+        let (h1, h2) = hash.finish128();
+        let h = ((h1 as u128) << 64) | (h2 as u128);
+        let mut builder = uuid::Builder::from_u128(h);
+        builder.set_variant(Variant::RFC4122);
+        builder.set_version(Version::Mac);
+        Timeuuid(CqlTimeuuid::from(builder.into_uuid()))
+    }
+
+    pub fn display(&self, buf: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        write!(buf, "{}", uuid::Uuid::from(self.0))
+    }
+}
+
+#[derive(Clone, Debug, Any)]
+pub struct Counter(pub i64);
+
+/// Cassandra `map` with keys and values of any type
+#[derive(Clone, Debug, Any)]
+pub struct Map(pub Vec<(CqlValue, CqlValue)>);
+
+/// Cassandra `set`
+#[derive(Clone, Debug, Any)]
+pub struct Set(pub Vec<CqlValue>);
+
 /// Returns the literal value stored in the `params` map under the key given as the first
 /// macro arg, and if not found, returns the expression from the second arg.
 pub fn param(
@@ -566,6 +2101,220 @@ pub fn float_to_i32(value: f64) -> Option<Int32> {
     int_to_i32(value as i64)
 }
 
+/// Converts a Rune float to f32 (Cassandra float)
+pub fn float_to_f32(value: f64) -> Float32 {
+    Float32(value as f32)
+}
+
+pub fn int_to_f32(value: i64) -> Float32 {
+    Float32(value as f32)
+}
+
+fn invalid_value(type_name: &str, value: impl Display) -> VmError {
+    VmError::panic(format!("Invalid {type_name} value: {value}"))
+}
+
+/// Creates a Cassandra `date` from the number of days since the Unix epoch
+/// or from a string in the `YYYY-MM-DD` format.
+pub fn date(value: Value) -> Result<Date, VmError> {
+    let days = match &value {
+        Value::Integer(days) => *days,
+        _ => {
+            let s = string_arg("date", &value)?;
+            let date = NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                .map_err(|e| invalid_value("date", format!("{s}: {e}")))?;
+            (date - NaiveDate::default()).num_days()
+        }
+    };
+    let days = days
+        .checked_add(1 << 31)
+        .and_then(|d| u32::try_from(d).ok())
+        .ok_or_else(|| invalid_value("date", days))?;
+    Ok(Date(CqlDate(days)))
+}
+
+/// Creates a Cassandra `time` from the number of nanoseconds since midnight
+/// or from a string in the `HH:MM:SS[.fraction]` format.
+pub fn time(value: Value) -> Result<Time, VmError> {
+    let nanos = match &value {
+        Value::Integer(nanos) => *nanos,
+        _ => {
+            let s = string_arg("time", &value)?;
+            let time = NaiveTime::parse_from_str(&s, "%H:%M:%S%.f")
+                .map_err(|e| invalid_value("time", format!("{s}: {e}")))?;
+            time.num_seconds_from_midnight() as i64 * 1_000_000_000 + time.nanosecond() as i64
+        }
+    };
+    if !(0..86_400_000_000_000).contains(&nanos) {
+        return Err(invalid_value("time", nanos));
+    }
+    Ok(Time(CqlTime(nanos)))
+}
+
+/// Creates a Cassandra `timestamp` from the number of milliseconds since the Unix epoch
+/// or from an RFC 3339 string, e.g. `2023-01-15T10:00:00Z`.
+pub fn timestamp(value: Value) -> Result<Timestamp, VmError> {
+    let millis = match &value {
+        Value::Integer(millis) => *millis,
+        _ => {
+            let s = string_arg("timestamp", &value)?;
+            DateTime::parse_from_rfc3339(&s)
+                .map_err(|e| invalid_value("timestamp", format!("{s}: {e}")))?
+                .timestamp_millis()
+        }
+    };
+    Ok(Timestamp(CqlTimestamp(millis)))
+}
+
+/// Creates a Cassandra `duration` from the number of months, days and nanoseconds.
+pub fn duration(months: i64, days: i64, nanoseconds: i64) -> Result<Duration, VmError> {
+    Ok(Duration(CqlDuration {
+        months: i32::try_from(months).map_err(|_| invalid_value("duration", months))?,
+        days: i32::try_from(days).map_err(|_| invalid_value("duration", days))?,
+        nanoseconds,
+    }))
+}
+
+/// Creates a Cassandra `decimal` from an integer or from a string like `-123.45`.
+pub fn decimal(value: Value) -> Result<Decimal, VmError> {
+    let (unscaled, scale) = match &value {
+        Value::Integer(v) => (*v as i128, 0),
+        _ => {
+            let s = string_arg("decimal", &value)?;
+            numbers::parse_decimal(&s).ok_or_else(|| invalid_value("decimal", s))?
+        }
+    };
+    let bytes = numbers::to_signed_bytes_be(unscaled);
+    Ok(Decimal(CqlDecimal::from_signed_be_bytes_and_exponent(
+        bytes, scale,
+    )))
+}
+
+/// Creates a Cassandra `varint` from an integer or from a string of decimal digits.
+pub fn varint(value: Value) -> Result<Varint, VmError> {
+    let v = match &value {
+        Value::Integer(v) => *v as i128,
+        _ => {
+            let s = string_arg("varint", &value)?;
+            s.parse().map_err(|_| invalid_value("varint", s))?
+        }
+    };
+    Ok(Varint(CqlVarint::from_signed_bytes_be(
+        numbers::to_signed_bytes_be(v),
+    )))
+}
+
+/// Creates a Cassandra `inet` from an IPv4 or IPv6 address string.
+pub fn inet(s: &str) -> Result<Inet, VmError> {
+    Ok(Inet(s.parse().map_err(|_| invalid_value("inet", s))?))
+}
+
+/// Creates a Cassandra `counter` value, to be used in counter updates.
+pub fn counter(value: i64) -> Counter {
+    Counter(value)
+}
+
+/// Creates a Cassandra `map` from an object or from a vector of key-value tuples.
+/// Unlike an object, a vector of tuples allows using keys of types other than text.
+pub fn map(value: Value) -> Result<Map, VmError> {
+    let to_scylla_value =
+        |v: &Value| bind::to_scylla_value(v).map_err(|e| VmError::panic(e.to_string()));
+    let mut entries = Vec::new();
+    match &value {
+        Value::Object(object) => {
+            for (k, v) in object.borrow_ref()?.iter() {
+                entries.push((CqlValue::Text(k.to_string()), to_scylla_value(v)?));
+            }
+        }
+        Value::Vec(vec) => {
+            for entry in vec.borrow_ref()?.iter() {
+                let entry = match entry {
+                    Value::Tuple(t) if t.borrow_ref()?.len() == 2 => t.clone(),
+                    _ => return Err(invalid_value("map entry", entry.type_info()?)),
+                };
+                let entry = entry.borrow_ref()?;
+                entries.push((to_scylla_value(&entry[0])?, to_scylla_value(&entry[1])?));
+            }
+        }
+        _ => return Err(invalid_value("map", value.type_info()?)),
+    }
+    Ok(Map(entries))
+}
+
+/// Creates a Cassandra `set` from a vector.
+pub fn set(value: Value) -> Result<Set, VmError> {
+    match &value {
+        Value::Vec(vec) => {
+            let mut elements = Vec::new();
+            for v in vec.borrow_ref()?.iter() {
+                elements.push(bind::to_scylla_value(v).map_err(|e| VmError::panic(e.to_string()))?);
+            }
+            Ok(Set(elements))
+        }
+        _ => Err(invalid_value("set", value.type_info()?)),
+    }
+}
+
+fn string_arg(type_name: &str, value: &Value) -> Result<String, VmError> {
+    match value {
+        Value::StaticString(v) => Ok(v.as_str().to_string()),
+        Value::String(v) => Ok(v.borrow_ref()?.as_str().to_string()),
+        _ => Err(invalid_value(type_name, value.type_info()?)),
+    }
+}
+
+/// Conversions between integers and the variable-length big-endian representation
+/// used by `varint` and `decimal`
+mod numbers {
+    /// Returns the shortest two's complement big-endian representation of the value.
+    pub fn to_signed_bytes_be(v: i128) -> Vec<u8> {
+        let bytes = v.to_be_bytes();
+        let mut start = 0;
+        while start < bytes.len() - 1 {
+            let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+                || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+            if !redundant {
+                break;
+            }
+            start += 1;
+        }
+        bytes[start..].to_vec()
+    }
+
+    /// Returns `None` if the value doesn't fit in 128 bits.
+    pub fn from_signed_bytes_be(bytes: &[u8]) -> Option<i128> {
+        if bytes.is_empty() || bytes.len() > 16 {
+            return None;
+        }
+        let fill = if bytes[0] & 0x80 != 0 { 0xFF } else { 0x00 };
+        let mut buf = [fill; 16];
+        buf[16 - bytes.len()..].copy_from_slice(bytes);
+        Some(i128::from_be_bytes(buf))
+    }
+
+    /// Parses a decimal number like `-123.45` into the unscaled value and the scale.
+    pub fn parse_decimal(s: &str) -> Option<(i128, i32)> {
+        let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+        if frac_part.starts_with(['-', '+']) || !frac_part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let unscaled = format!("{int_part}{frac_part}").parse().ok()?;
+        Some((unscaled, frac_part.len() as i32))
+    }
+
+    pub fn format_decimal(unscaled: i128, scale: i32) -> String {
+        if scale <= 0 {
+            return format!("{}{}", unscaled, "0".repeat(-scale as usize));
+        }
+        let scale = scale as usize;
+        let digits = unscaled.unsigned_abs().to_string();
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        let sign = if unscaled < 0 { "-" } else { "" };
+        format!("{sign}{int_part}.{frac_part}")
+    }
+}
+
 /// Computes a hash of an integer value `i`.
 /// Returns a value in range `0..i64::MAX`.
 pub fn hash(i: i64) -> i64 {
@@ -660,3 +2409,153 @@ pub fn read_resource_lines(path: &str) -> io::Result<Vec<String>> {
         .map(|s| s.to_string())
         .collect_vec())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::context::{bind, date, lwt_applied, numbers, CassError, CassErrorKind};
+    use rune::Value;
+    use scylla::frame::response::result::{ColumnSpec, ColumnType, CqlValue, Row, TableSpec};
+    use scylla::statement::Consistency;
+    use scylla::transport::errors::{DbError, QueryError};
+    use scylla::QueryResult;
+
+    #[test]
+    pub fn signed_bytes_round_trip() {
+        for v in [
+            0,
+            1,
+            -1,
+            127,
+            128,
+            -128,
+            -129,
+            255,
+            65535,
+            i64::MAX as i128,
+            i128::MIN,
+        ] {
+            let bytes = numbers::to_signed_bytes_be(v);
+            assert_eq!(numbers::from_signed_bytes_be(&bytes), Some(v));
+        }
+        assert_eq!(numbers::to_signed_bytes_be(128), vec![0x00, 0x80]);
+        assert_eq!(numbers::to_signed_bytes_be(-128), vec![0x80]);
+    }
+
+    #[test]
+    pub fn parse_and_format_decimal() {
+        assert_eq!(numbers::parse_decimal("-123.45"), Some((-12345, 2)));
+        assert_eq!(numbers::parse_decimal("7"), Some((7, 0)));
+        assert_eq!(numbers::parse_decimal("1.-5"), None);
+        assert_eq!(numbers::format_decimal(-12345, 2), "-123.45");
+        assert_eq!(numbers::format_decimal(5, 3), "0.005");
+        assert_eq!(numbers::format_decimal(5, -2), "500");
+    }
+
+    #[test]
+    pub fn convert_to_declared_type() {
+        assert_eq!(
+            bind::convert(CqlValue::BigInt(5), &ColumnType::TinyInt),
+            Ok(CqlValue::TinyInt(5))
+        );
+        assert!(bind::convert(CqlValue::BigInt(300), &ColumnType::TinyInt).is_err());
+        assert!(bind::convert(CqlValue::Text("a".to_string()), &ColumnType::Int).is_err());
+        assert_eq!(
+            bind::convert(
+                CqlValue::List(vec![CqlValue::BigInt(1)]),
+                &ColumnType::Set(Box::new(ColumnType::Int))
+            ),
+            Ok(CqlValue::Set(vec![CqlValue::Int(1)]))
+        );
+    }
+
+    #[test]
+    pub fn convert_udt_fields_in_declared_order() {
+        let typ = ColumnType::UserDefinedType {
+            type_name: "t".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Text),
+            ],
+        };
+        let value = CqlValue::UserDefinedType {
+            keyspace: "unknown".to_string(),
+            type_name: "unknown".to_string(),
+            fields: vec![
+                ("b".to_string(), Some(CqlValue::Text("x".to_string()))),
+                ("a".to_string(), Some(CqlValue::BigInt(1))),
+            ],
+        };
+        assert_eq!(
+            bind::convert(value, &typ),
+            Ok(CqlValue::UserDefinedType {
+                keyspace: "ks".to_string(),
+                type_name: "t".to_string(),
+                fields: vec![
+                    ("a".to_string(), Some(CqlValue::Int(1))),
+                    ("b".to_string(), Some(CqlValue::Text("x".to_string()))),
+                ],
+            })
+        );
+    }
+
+    fn query_result(column: &str, value: CqlValue) -> QueryResult {
+        let mut rs = QueryResult::default();
+        rs.col_specs = vec![ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: "ks".to_string(),
+                table_name: "t".to_string(),
+            },
+            name: column.to_string(),
+            typ: ColumnType::Boolean,
+        }];
+        rs.rows = Some(vec![Row {
+            columns: vec![Some(value)],
+        }]);
+        rs
+    }
+
+    #[test]
+    pub fn lwt_applied_flag() {
+        let applied = query_result("[applied]", CqlValue::Boolean(true));
+        let rejected = query_result("[applied]", CqlValue::Boolean(false));
+        let not_conditional = query_result("flag", CqlValue::Boolean(true));
+        assert_eq!(lwt_applied(&applied), Some(true));
+        assert_eq!(lwt_applied(&rejected), Some(false));
+        assert_eq!(lwt_applied(&not_conditional), None);
+        assert_eq!(lwt_applied(&QueryResult::default()), None);
+    }
+
+    #[test]
+    pub fn classify_errors_by_category() {
+        let error = |err| CassError::query_execution_error("SELECT", &[], err);
+        let timeout = error(QueryError::TimeoutError);
+        assert!(matches!(timeout.0, CassErrorKind::Overloaded(_, _)));
+        assert!(timeout.is_transient());
+
+        let unavailable = error(QueryError::DbError(
+            DbError::Unavailable {
+                consistency: Consistency::Quorum,
+                required: 2,
+                alive: 1,
+            },
+            "unavailable".to_string(),
+        ));
+        assert!(matches!(unavailable.0, CassErrorKind::QueryExecution(_, _)));
+        assert!(unavailable.is_transient());
+
+        let invalid = error(QueryError::DbError(
+            DbError::SyntaxError,
+            "syntax".to_string(),
+        ));
+        assert!(matches!(invalid.0, CassErrorKind::QueryExecution(_, _)));
+        assert!(!invalid.is_transient());
+    }
+
+    #[test]
+    fn date_out_of_range() {
+        assert_eq!(date(Value::Integer(0)).unwrap().0 .0, 1 << 31);
+        assert!(date(Value::Integer(i64::MAX)).is_err());
+        assert!(date(Value::Integer(i64::MIN)).is_err());
+    }
+}
@@ -3,15 +3,24 @@
 use futures::channel::mpsc::{channel, Receiver, Sender};
 use futures::{SinkExt, Stream, StreamExt};
 use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use status_line::StatusLine;
 use std::cmp::max;
 use std::future::ready;
+use std::future::Future;
 use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tokio_stream::wrappers::IntervalStream;
 
+use crate::concurrency::ConcurrencyController;
+use crate::config::{Arrivals, ErrorConf, LatencySlo, RateProfile};
 use crate::error::Result;
+use crate::workload::CycleExecutor;
 use crate::{
     BenchmarkStats, BoundedCycleCounter, InterruptHandler, Interval, Progress, Recorder, Sampler,
     Workload, WorkloadStats,
@@ -23,25 +32,140 @@ fn interval_stream(rate: f64) -> IntervalStream {
     IntervalStream::new(tokio::time::interval(interval))
 }
 
+/// Scheduled cycles started later than this after their scheduled time are counted as late.
+/// Leaves a margin for the resolution of the timer.
+const LATE_LAUNCH_THRESHOLD: Duration = Duration::from_millis(5);
+
+/// Maximum number of cycles in progress per thread in the open-loop mode.
+/// Scheduled cycles exceeding this limit are dropped, so a stalled server can't make
+/// the client run out of memory.
+const OPEN_LOOP_MAX_PENDING: usize = 65536;
+
+/// The longest time the rate is assumed constant when computing the time of the next event.
+const RATE_PROFILE_STEP: Duration = Duration::from_millis(10);
+
+/// Returns a stream emitting `rate` events per second on average,
+/// with the intervals between the events drawn from the `arrivals` distribution.
+/// Each event carries the time it was scheduled at.
+fn random_interval_stream(
+    rate: f64,
+    arrivals: Arrivals,
+    rng: StdRng,
+) -> Pin<Box<dyn Stream<Item = Instant> + Send>> {
+    let start = tokio::time::Instant::now();
+    // Time of the next event since start; the first event is emitted immediately.
+    let initial_state = (Duration::ZERO, rng);
+    let stream = futures::stream::unfold(initial_state, move |(time, mut rng)| async move {
+        let scheduled_time = start + time;
+        tokio::time::sleep_until(scheduled_time).await;
+        let interval = Duration::from_secs_f64(arrivals.next_interval(&mut rng) / rate);
+        Some((scheduled_time.into_std(), (time + interval, rng)))
+    });
+    Box::pin(stream)
+}
+
+/// Returns a stream emitting events at the rate changing over time according to the profile.
+/// The stream emits `share` of the events of the profile.
+/// Streams sharing the same `start` time follow the profile in sync.
+/// The intervals between the events are drawn from the `arrivals` distribution.
+/// Each event carries the time it was scheduled at.
+fn rate_profile_stream(
+    profile: Arc<RateProfile>,
+    duration: Option<Duration>,
+    share: f64,
+    start: tokio::time::Instant,
+    arrivals: Arrivals,
+    rng: StdRng,
+) -> Pin<Box<dyn Stream<Item = Instant> + Send>> {
+    // Time of the last event since start, and the number of events expected at the current
+    // rate that must pass before the next event. The first event is emitted immediately.
+    let initial_state = (Duration::ZERO, 0.0, rng);
+    let stream = futures::stream::unfold(initial_state, move |(mut time, mut missing, mut rng)| {
+        let profile = profile.clone();
+        async move {
+            loop {
+                let rate = profile.rate_at(time, duration) * share;
+                if missing <= 0.0 {
+                    break;
+                }
+                // End the stream if the rate dropped to zero for good,
+                // e.g. at the last point of a profile loaded from a file:
+                let end = profile.constant_after(duration);
+                if rate <= 0.0 && end.is_some_and(|end| time >= end) {
+                    return None;
+                }
+                let step = RATE_PROFILE_STEP.as_secs_f64();
+                if rate * step >= missing {
+                    time += Duration::from_secs_f64(missing / rate);
+                    break;
+                }
+                missing -= rate * step;
+                time += RATE_PROFILE_STEP;
+                // Don't wait for the next event forever if the rate dropped to zero at the end:
+                if duration.is_some_and(|d| time > d) {
+                    return None;
+                }
+            }
+            let scheduled_time = start + time;
+            tokio::time::sleep_until(scheduled_time).await;
+            let missing = arrivals.next_interval(&mut rng);
+            Some((scheduled_time.into_std(), (time, missing, rng)))
+        }
+    });
+    Box::pin(stream)
+}
+
+/// Resolves to the scheduled start time of the next cycle as soon as
+/// the number of pending cycles drops below the limit.
+/// Doesn't register for a wakeup by itself; relies on being polled again
+/// after one of the pending cycles completes.
+struct ConcurrencyGate {
+    /// The number of pending cycles and the limit; `None` means no limit
+    limit: Option<(Arc<AtomicUsize>, Arc<AtomicUsize>)>,
+    scheduled_time: Option<Instant>,
+}
+
+impl Future for ConcurrencyGate {
+    type Output = Option<Instant>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &self.limit {
+            Some((pending, limit))
+                if pending.load(Ordering::Relaxed) >= limit.load(Ordering::Relaxed) =>
+            {
+                Poll::Pending
+            }
+            _ => Poll::Ready(self.scheduled_time),
+        }
+    }
+}
+
 /// Runs a stream of workload cycles till completion in the context of the current task.
 /// Periodically sends workload statistics to the `out` channel.
 ///
 /// # Parameters
-/// - stream: a stream of cycle numbers; None means the end of the stream
+/// - stream: a stream of the scheduled start times of the cycles;
+///   `None` items mean the cycles are not scheduled and start as soon as possible
 /// - workload: defines the function to call
 /// - cycle_counter: shared cycle numbers provider
 /// - concurrency: the maximum number of pending workload calls
+/// - open_loop_max_pending: if set, the cycles are started at their scheduled times regardless
+///   of the number of pending calls, up to given count; `concurrency` is ignored
+/// - concurrency_limit: the maximum number of pending workload calls that can change
+///   while the stream is running; must not exceed `concurrency`
 /// - sampling: controls when to output workload statistics
 /// - progress: progress bar notified about each successful cycle
 /// - interrupt: allows for terminating the stream early
 /// - out: the channel to receive workload statistics
 ///
 #[allow(clippy::too_many_arguments)] // todo: refactor
-async fn run_stream<T>(
-    stream: impl Stream<Item = T> + std::marker::Unpin,
-    workload: Workload,
+async fn run_stream(
+    stream: impl Stream<Item = Option<Instant>> + std::marker::Unpin,
+    workload: impl CycleExecutor,
     cycle_counter: BoundedCycleCounter,
     concurrency: NonZeroUsize,
+    open_loop_max_pending: Option<usize>,
+    concurrency_limit: Option<Arc<AtomicUsize>>,
     sampling: Interval,
     interrupt: Arc<InterruptHandler>,
     progress: Arc<StatusLine<Progress>>,
@@ -52,13 +176,48 @@ async fn run_stream<T>(
     let mut iter_counter = cycle_counter;
     let mut sampler = Sampler::new(iter_counter.duration, sampling, &workload, &mut out);
 
+    // In the closed-loop mode, buffer_unordered doesn't poll the stream for the next cycle
+    // until one of the pending cycles completes, so the cycles get delayed.
+    // In the open-loop mode, the stream is always polled, and the cycles that can't be started
+    // are dropped instead.
+    let pending = Arc::new(AtomicUsize::new(0));
+    let open_loop = open_loop_max_pending.is_some();
+    let max_pending = open_loop_max_pending.unwrap_or(concurrency.get());
     let mut result_stream = stream
-        .map(|_| iter_counter.next())
+        .filter(|_| {
+            let dropped = open_loop && pending.load(Ordering::Relaxed) >= max_pending;
+            if dropped {
+                workload.launch_dropped();
+            }
+            ready(!dropped)
+        })
+        // With adaptive concurrency, holds the next cycle while too many cycles are pending:
+        .then(|scheduled_time| ConcurrencyGate {
+            limit: concurrency_limit
+                .as_ref()
+                .map(|limit| (pending.clone(), limit.clone())),
+            scheduled_time,
+        })
+        .map(|scheduled_time| iter_counter.next().map(|i| (i, scheduled_time)))
         .take_while(|i| ready(i.is_some()))
         // unconstrained to workaround quadratic complexity of buffer_unordered ()
-        .map(|i| tokio::task::unconstrained(workload.run(i.unwrap())))
-        .buffer_unordered(concurrency.get())
-        .inspect(|_| progress.tick());
+        .map(|i| {
+            let (cycle, scheduled_time) = i.unwrap();
+            // Uses the clock of the runtime, the same as the scheduler of the cycles:
+            let now = tokio::time::Instant::now().into_std();
+            if scheduled_time
+                .is_some_and(|t| now.saturating_duration_since(t) > LATE_LAUNCH_THRESHOLD)
+            {
+                workload.launch_late();
+            }
+            pending.fetch_add(1, Ordering::Relaxed);
+            tokio::task::unconstrained(workload.run(cycle, scheduled_time))
+        })
+        .buffer_unordered(if open_loop { usize::MAX } else { max_pending })
+        .inspect(|_| {
+            pending.fetch_sub(1, Ordering::Relaxed);
+            progress.tick()
+        });
 
     while let Some(res) = result_stream.next().await {
         match res {
@@ -82,9 +241,15 @@ async fn run_stream<T>(
 /// The task updates the `progress` bar after each successful cycle.
 ///
 /// Returns a stream where workload statistics are published.
+#[allow(clippy::too_many_arguments)]
 fn spawn_stream(
     concurrency: NonZeroUsize,
+    open_loop: bool,
+    concurrency_limit: Option<Arc<AtomicUsize>>,
     rate: Option<f64>,
+    arrivals: Arrivals,
+    rng: StdRng,
+    rate_profile: Option<Pin<Box<dyn Stream<Item = Instant> + Send>>>,
     sampling: Interval,
     workload: Workload,
     iter_counter: BoundedCycleCounter,
@@ -92,16 +257,50 @@ fn spawn_stream(
     progress: Arc<StatusLine<Progress>>,
 ) -> Receiver<Result<WorkloadStats>> {
     let (tx, rx) = channel(1);
+    let open_loop_max_pending = open_loop.then_some(OPEN_LOOP_MAX_PENDING);
 
     tokio::spawn(async move {
-        match rate {
-            Some(rate) => {
-                let stream = interval_stream(rate);
+        match (rate_profile, rate) {
+            (Some(stream), _) => {
+                run_stream(
+                    stream.map(Some),
+                    workload,
+                    iter_counter,
+                    concurrency,
+                    open_loop_max_pending,
+                    concurrency_limit,
+                    sampling,
+                    interrupt,
+                    progress,
+                    tx,
+                )
+                .await
+            }
+            (None, Some(rate)) if arrivals == Arrivals::Fixed => {
+                let stream = interval_stream(rate).map(|t| Some(t.into_std()));
+                run_stream(
+                    stream,
+                    workload,
+                    iter_counter,
+                    concurrency,
+                    open_loop_max_pending,
+                    concurrency_limit,
+                    sampling,
+                    interrupt,
+                    progress,
+                    tx,
+                )
+                .await
+            }
+            (None, Some(rate)) => {
+                let stream = random_interval_stream(rate, arrivals, rng).map(Some);
                 run_stream(
                     stream,
                     workload,
                     iter_counter,
                     concurrency,
+                    open_loop_max_pending,
+                    concurrency_limit,
                     sampling,
                     interrupt,
                     progress,
@@ -109,13 +308,15 @@ fn spawn_stream(
                 )
                 .await
             }
-            None => {
-                let stream = futures::stream::repeat_with(|| ());
+            (None, None) => {
+                let stream = futures::stream::repeat_with(|| None);
                 run_stream(
                     stream,
                     workload,
                     iter_counter,
                     concurrency,
+                    open_loop_max_pending,
+                    concurrency_limit,
                     sampling,
                     interrupt,
                     progress,
@@ -149,10 +350,23 @@ pub struct ExecutionOptions {
     pub duration: Interval,
     /// Maximum rate of requests in requests per second, `None` means no limit
     pub rate: Option<f64>,
+    /// Varies the rate over time; takes precedence over `rate`
+    pub rate_profile: Option<RateProfile>,
     /// Number of parallel threads of execution
     pub threads: NonZeroUsize,
     /// Number of outstanding async requests per each thread
     pub concurrency: NonZeroUsize,
+    /// Start the cycles at their scheduled times regardless of the number of outstanding requests
+    pub open_loop: bool,
+    /// Distribution of the intervals between the scheduled starts of the cycles
+    pub arrivals: Arrivals,
+    /// Seed of the random intervals between the cycles
+    pub arrival_seed: u64,
+    /// Adjusts the number of outstanding requests to keep the response times below the objective;
+    /// `concurrency` becomes the upper limit
+    pub adaptive_concurrency: Option<LatencySlo>,
+    /// Limits of errors that interrupt the execution
+    pub errors: ErrorConf,
 }
 
 /// Executes the given function many times in parallel.
@@ -164,6 +378,8 @@ pub struct ExecutionOptions {
 ///   - `count`: number of cycles
 ///   - `exec_options`: controls execution options such as parallelism level and rate
 ///   - `workload`: encapsulates a set of queries to execute
+///   - `timeline`: optional recorder spanning multiple executions, e.g. all phases of a scenario;
+///     if given, the printed samples are timed relative to its start
 pub async fn par_execute(
     name: &str,
     exec_options: &ExecutionOptions,
@@ -171,6 +387,7 @@ pub async fn par_execute(
     workload: Workload,
     signals: Arc<InterruptHandler>,
     show_progress: bool,
+    mut timeline: Option<&mut Recorder>,
 ) -> Result<BenchmarkStats> {
     let thread_count = exec_options.threads.get();
     let concurrency = exec_options.concurrency;
@@ -188,11 +405,35 @@ pub async fn par_execute(
     let deadline = BoundedCycleCounter::new(exec_options.duration);
     let mut streams = Vec::with_capacity(thread_count);
     let mut stats = Recorder::start(rate, concurrency);
+    let rate_profile = exec_options.rate_profile.clone().map(Arc::new);
+    let duration = match exec_options.duration {
+        Interval::Time(d) => Some(d),
+        _ => None,
+    };
+    let start = tokio::time::Instant::now();
+    let mut controller = exec_options
+        .adaptive_concurrency
+        .map(|slo| ConcurrencyController::new(&slo, concurrency));
+    let concurrency_limit = controller
+        .as_ref()
+        .map(|c| Arc::new(AtomicUsize::new(c.limit())));
 
-    for _ in 0..thread_count {
+    for i in 0..thread_count {
+        // Each thread draws different intervals, but the same in each run with the same seed:
+        let rng = StdRng::seed_from_u64(exec_options.arrival_seed.wrapping_add(i as u64));
+        let share = 1.0 / thread_count as f64;
+        let arrivals = exec_options.arrivals;
+        let profile_stream = rate_profile
+            .clone()
+            .map(|p| rate_profile_stream(p, duration, share, start, arrivals, rng.clone()));
         let s = spawn_stream(
             concurrency,
+            exec_options.open_loop,
+            concurrency_limit.clone(),
             rate.map(|r| r / (thread_count as f64)),
+            arrivals,
+            rng,
+            profile_stream,
             sampling,
             workload.clone()?,
             deadline.share(),
@@ -212,13 +453,169 @@ pub async fn par_execute(
             break;
         }
 
-        let aggregate = stats.record(&partial_stats);
+        let limit = controller.as_ref().map(|c| c.limit());
+        let aggregate = match timeline.as_deref_mut() {
+            Some(timeline) => {
+                stats.record(&partial_stats, limit);
+                timeline.record(&partial_stats, limit)
+            }
+            None => stats.record(&partial_stats, limit),
+        };
+        if let (Some(controller), Some(concurrency_limit)) = (&mut controller, &concurrency_limit) {
+            let limit = controller.update(aggregate);
+            concurrency_limit.store(limit, Ordering::Relaxed);
+        }
         if sampling.is_bounded() {
             progress.set_visible(false);
             println!("{aggregate}");
             progress.set_visible(show_progress);
         }
+
+        // Errors are counted since the start of the timeline, e.g. across all phases:
+        let totals = timeline.as_deref().unwrap_or(&stats);
+        // The request count includes the failed requests:
+        let budget_exceeded = exec_options
+            .errors
+            .budget_exceeded(totals.error_count, totals.request_count);
+        if let Some(reason) = budget_exceeded.filter(|_| !signals.is_interrupted()) {
+            progress.set_visible(false);
+            eprintln!("error: Stopping the run: {reason}");
+            progress.set_visible(show_progress);
+            signals.interrupt_on_errors();
+        }
     }
 
     Ok(stats.finish())
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use futures::channel::mpsc::channel;
+    use futures::StreamExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use status_line::StatusLine;
+
+    use crate::config::{Arrivals, RateProfile};
+    use crate::error::LatteError;
+    use crate::exec::{random_interval_stream, rate_profile_stream, run_stream};
+    use crate::workload::{CycleExecutor, FnStats, WorkloadStats};
+    use crate::{BoundedCycleCounter, InterruptHandler, Interval, Progress, SessionStats};
+
+    /// Workload with slow responses, which additionally blocks the thread
+    /// for a while in the `stall_cycle`.
+    struct SlowWorkload {
+        response_time: Duration,
+        stall_cycle: u64,
+        stall_time: Duration,
+        stats: Mutex<FnStats>,
+    }
+
+    impl CycleExecutor for SlowWorkload {
+        async fn run(
+            &self,
+            cycle: u64,
+            _scheduled_start_time: Option<Instant>,
+        ) -> Result<(u64, Instant), LatteError> {
+            if cycle == self.stall_cycle {
+                // Moves the clock forward without letting other tasks run:
+                tokio::time::advance(self.stall_time).await;
+            }
+            tokio::time::sleep(self.response_time).await;
+            let mut stats = self.stats.lock().unwrap();
+            stats.operation_completed(self.response_time);
+            Ok((cycle, tokio::time::Instant::now().into_std()))
+        }
+
+        fn launch_late(&self) {
+            self.stats.lock().unwrap().late_launch_count += 1;
+        }
+
+        fn launch_dropped(&self) {
+            self.stats.lock().unwrap().dropped_launch_count += 1;
+        }
+
+        fn reset(&self, _start_time: Instant) {
+            *self.stats.lock().unwrap() = FnStats::default();
+        }
+
+        fn take_stats(&self, end_time: Instant) -> WorkloadStats {
+            WorkloadStats {
+                start_time: end_time,
+                end_time,
+                function_stats: std::mem::take(&mut self.stats.lock().unwrap()),
+                functions: BTreeMap::new(),
+                session_stats: SessionStats::new(),
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_profile_stream_ends_when_rate_drops_to_zero() {
+        let profile = RateProfile::Points {
+            path: PathBuf::new(),
+            points: vec![(0.0, 100.0), (1.0, 0.0)],
+        };
+        let start = tokio::time::Instant::now();
+        let rng = StdRng::seed_from_u64(0);
+        let stream = rate_profile_stream(Arc::new(profile), None, 1.0, start, Arrivals::Fixed, rng);
+        let count = tokio::time::timeout(Duration::from_secs(10), stream.count())
+            .await
+            .expect("Stream didn't end");
+        assert!((45..=55).contains(&count), "{count}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn open_loop_starts_cycles_at_scheduled_times() {
+        let count = 1000;
+        let workload = SlowWorkload {
+            response_time: Duration::from_millis(100),
+            stall_cycle: 500,
+            stall_time: Duration::from_millis(200),
+            stats: Mutex::new(FnStats::default()),
+        };
+        let rng = StdRng::seed_from_u64(0);
+        let stream = random_interval_stream(1000.0, Arrivals::Fixed, rng).map(Some);
+        let progress = Progress::with_count("test".to_string(), count);
+        let progress_opts = status_line::Options {
+            initially_visible: false,
+            ..Default::default()
+        };
+        let (tx, mut rx) = channel(1);
+        let start = tokio::time::Instant::now();
+        run_stream(
+            stream,
+            workload,
+            BoundedCycleCounter::new(Interval::Count(count)),
+            NonZeroUsize::new(1).unwrap(),
+            Some(200),
+            None,
+            Interval::Unbounded,
+            Arc::new(InterruptHandler::install()),
+            Arc::new(StatusLine::with_options(progress, progress_opts)),
+            tx,
+        )
+        .await;
+        let elapsed = start.elapsed();
+        let stats = rx.next().await.unwrap().unwrap().function_stats;
+
+        assert_eq!(stats.call_count, count);
+        // The cycles scheduled during the stall were started late or dropped:
+        assert!(stats.late_launch_count > 0);
+        assert!(stats.dropped_launch_count > 0);
+        // About 100 cycles are pending at any time, far more than the concurrency of 1,
+        // yet the cycles are launched at the scheduled rate of 1 per ms:
+        let scheduled_count = count + stats.dropped_launch_count;
+        let expected = Duration::from_millis(scheduled_count) + Duration::from_millis(100);
+        assert!(
+            elapsed < expected + Duration::from_millis(50),
+            "{elapsed:?}"
+        );
+    }
+}
@@ -11,6 +11,12 @@ use serde::{Deserialize, Deserializer, Serialize};
 /// a base64 encoded string we can store in JSON report.
 pub struct SerializableHistogram(pub Histogram<u64>);
 
+impl Default for SerializableHistogram {
+    fn default() -> Self {
+        SerializableHistogram(Histogram::new(3).unwrap())
+    }
+}
+
 impl Serialize for SerializableHistogram {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1,9 +1,10 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-/// Notifies about received Ctrl-C signal
+/// Notifies about received Ctrl-C signal or about exceeding the limits of errors
 pub struct InterruptHandler {
     interrupted: Arc<AtomicBool>,
+    too_many_errors: AtomicBool,
 }
 
 impl InterruptHandler {
@@ -11,11 +12,25 @@ impl InterruptHandler {
         let cell = Arc::new(AtomicBool::new(false));
         let cell_ref = cell.clone();
         let _ = ctrlc::set_handler(move || cell_ref.store(true, Ordering::Relaxed));
-        InterruptHandler { interrupted: cell }
+        InterruptHandler {
+            interrupted: cell,
+            too_many_errors: AtomicBool::new(false),
+        }
     }
 
-    /// Returns true if Ctrl-C was pressed
+    /// Interrupts the run because the number of errors exceeded the limits
+    pub fn interrupt_on_errors(&self) {
+        self.too_many_errors.store(true, Ordering::Relaxed);
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if Ctrl-C was pressed or the run was interrupted because of errors
     pub fn is_interrupted(&self) -> bool {
         self.interrupted.load(Ordering::Relaxed)
     }
+
+    /// Returns true if the run was interrupted because of errors
+    pub fn is_interrupted_on_errors(&self) -> bool {
+        self.too_many_errors.load(Ordering::Relaxed)
+    }
 }
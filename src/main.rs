@@ -11,13 +11,14 @@ use hdrhistogram::serialization::{interval_log, V2DeflateSerializer};
 use itertools::Itertools;
 use rune::Source;
 use search_path::SearchPath;
+use strum::IntoEnumIterator;
 use tokio::runtime::{Builder, Runtime};
 
 use config::RunCommand;
 
 use crate::config::{
-    AppConfig, Command, ConnectionConf, HdrCommand, Interval, LoadCommand, SchemaCommand,
-    ShowCommand,
+    AppConfig, Arrivals, Command, ConnectionConf, ErrorConf, ErrorKind, HdrCommand, Interval,
+    LatencySlo, LoadCommand, PhaseConf, SchemaCommand, ShowCommand, TracingConf,
 };
 use crate::context::*;
 use crate::context::{CassError, CassErrorKind, Context, SessionStats};
@@ -27,11 +28,14 @@ use crate::exec::{par_execute, ExecutionOptions};
 use crate::interrupt::InterruptHandler;
 use crate::plot::plot_graph;
 use crate::progress::Progress;
-use crate::report::{Report, RunConfigCmp};
+use crate::report::{PhaseReport, PhasesCmp, Report, RunConfigCmp};
 use crate::sampler::Sampler;
-use crate::stats::{BenchmarkCmp, BenchmarkStats, Recorder};
+use crate::search::{Probe, RateSearch, SearchReport};
+use crate::stats::{BenchmarkCmp, BenchmarkStats, Percentile, Recorder};
+use crate::trace::RequestTrace;
 use crate::workload::{FnRef, Program, Workload, WorkloadStats, LOAD_FN};
 
+mod concurrency;
 mod config;
 mod context;
 mod cycle;
@@ -43,11 +47,16 @@ mod plot;
 mod progress;
 mod report;
 mod sampler;
+mod search;
 mod stats;
+mod trace;
 mod workload;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Exit code of a run interrupted because of exceeding the limits of errors
+const ERRORS_EXIT_CODE: i32 = 3;
+
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
@@ -107,9 +116,13 @@ fn find_workload(workload: &Path) -> PathBuf {
 
 /// Connects to the server and returns the session
 async fn connect(conf: &ConnectionConf) -> Result<(Context, Option<ClusterInfo>)> {
+    if !conf.profiles.iter().map(|p| &p.name).all_unique() {
+        eprintln!("error: Each execution profile must have a unique name.");
+        exit(255);
+    }
     eprintln!("info: Connecting to {:?}... ", conf.addresses);
     let session = context::connect(conf).await?;
-    let session = Context::new(session);
+    let session = Context::new(session, conf);
     let cluster_info = session.cluster_info().await?;
     eprintln!(
         "info: Connected to {} running Cassandra version {}",
@@ -173,12 +186,23 @@ async fn load(conf: LoadCommand) -> Result<()> {
 
     let interrupt = Arc::new(InterruptHandler::install());
     eprintln!("info: Loading data...");
-    let loader = Workload::new(session.clone()?, program.clone(), FnRef::new(LOAD_FN));
+    let loader = Workload::new(
+        session.clone()?,
+        program.clone(),
+        &[(FnRef::new(LOAD_FN), 1.0)],
+        &[ErrorKind::Overloaded],
+    );
     let load_options = ExecutionOptions {
         duration: config::Interval::Count(load_count),
         rate: conf.rate,
+        rate_profile: None,
         threads: conf.threads,
         concurrency: conf.concurrency,
+        open_loop: false,
+        arrivals: Arrivals::Fixed,
+        arrival_seed: 0,
+        adaptive_concurrency: None,
+        errors: ErrorConf::default(),
     };
     let result = par_execute(
         "Loading...",
@@ -187,11 +211,12 @@ async fn load(conf: LoadCommand) -> Result<()> {
         loader,
         interrupt.clone(),
         !conf.quiet,
+        None,
     )
     .await?;
 
     if result.error_count > 0 {
-        for e in result.errors {
+        for e in result.error_stats.examples.values().flatten() {
             eprintln!("error: {e}");
         }
         eprintln!("error: Errors encountered when loading data. Some data might be missing.");
@@ -202,15 +227,57 @@ async fn load(conf: LoadCommand) -> Result<()> {
 
 async fn run(conf: RunCommand) -> Result<()> {
     let mut conf = conf.set_timestamp_if_empty();
-    let function = FnRef::new(conf.function.as_str());
     let compare = conf.baseline.as_ref().map(|p| load_report_or_abort(p));
 
     let mut program = load_workload_script(&conf.workload, &conf.params)?;
-    if !program.has_function(&function) {
-        eprintln!(
-            "error: Function {} not found in the workload script.",
-            conf.function.as_str()
-        );
+    let phases = conf.resolved_phases();
+    for phase in phases.iter() {
+        for f in phase.functions.iter() {
+            if !program.has_function(&FnRef::new(f.name.as_str())) {
+                eprintln!(
+                    "error: Function {} not found in the workload script.",
+                    f.name
+                );
+                exit(255);
+            }
+        }
+        if !phase.functions.iter().map(|f| &f.name).all_unique() {
+            eprintln!("error: Each function can be given only once.");
+            exit(255);
+        }
+    }
+    if !phases.iter().map(|p| &p.name).all_unique() {
+        eprintln!("error: Each phase must have a unique name.");
+        exit(255);
+    }
+    let find_percentile = |slo: &LatencySlo| {
+        search::find_percentile(slo).unwrap_or_else(|| {
+            let percentiles = Percentile::iter()
+                .map(|p| format!("p{}", p.value()))
+                .join(", ");
+            eprintln!("error: Unsupported objective percentile. Available: {percentiles}.");
+            exit(255);
+        })
+    };
+    let search_percentile = conf.search.slo.as_ref().map(find_percentile);
+    if let Err(e) = conf.search.validate() {
+        eprintln!("error: {e}.");
+        exit(255);
+    }
+    if let Some(slo) = conf.adaptive_concurrency.as_ref() {
+        find_percentile(slo);
+    }
+    if let Some(profile) = conf.rate_profile.as_ref().filter(|p| p.needs_duration()) {
+        let count_based = |p: &PhaseConf| matches!(p.duration, Some(Interval::Count(_)));
+        if phases.iter().any(|p| p.rate.is_none() && count_based(p)) {
+            eprintln!("error: Rate profile {profile} requires a time-based duration.");
+            exit(255);
+        }
+    }
+
+    let unscheduled = |p: &PhaseConf| p.rate.is_none() && conf.rate_profile.is_none();
+    if conf.open_loop && conf.search.slo.is_none() && phases.iter().any(unscheduled) {
+        eprintln!("error: Open-loop execution requires a rate or a rate profile.");
         exit(255);
     }
 
@@ -227,24 +294,33 @@ async fn run(conf: RunCommand) -> Result<()> {
             exit(255);
         }
     }
+    conf.statements = session.statement_confs();
+    session.set_tracing(conf.tracing.clone());
 
-    let runner = Workload::new(session.clone()?, program.clone(), function);
     let interrupt = Arc::new(InterruptHandler::install());
     if conf.warmup_duration.is_not_zero() {
         eprintln!("info: Warming up...");
+        let first_phase = &phases[0];
         let warmup_options = ExecutionOptions {
             duration: conf.warmup_duration,
             rate: None,
+            rate_profile: None,
             threads: conf.threads,
-            concurrency: conf.concurrency,
+            concurrency: first_phase.concurrency.unwrap(),
+            open_loop: false,
+            arrivals: Arrivals::Fixed,
+            arrival_seed: 0,
+            adaptive_concurrency: None,
+            errors: ErrorConf::default(),
         };
         par_execute(
             "Warming up...",
             &warmup_options,
             Interval::Unbounded,
-            runner.clone()?,
+            phase_workload(&conf, &session, &program, first_phase)?,
             interrupt.clone(),
             !conf.quiet,
+            None,
         )
         .await?;
     }
@@ -263,37 +339,50 @@ async fn run(conf: RunCommand) -> Result<()> {
         }
     );
 
-    let exec_options = ExecutionOptions {
-        duration: conf.run_duration,
-        concurrency: conf.concurrency,
-        rate: conf.rate,
-        threads: conf.threads,
+    report::print_log_header(conf.adaptive_concurrency.is_some());
+    let mut search_report = None;
+    let (stats, phase_reports) = if let Some(percentile) = search_percentile {
+        let (stats, phases, search) = search_rate(
+            &conf, &phases[0], percentile, &session, &program, &interrupt,
+        )
+        .await?;
+        search_report = Some(search);
+        (stats, phases)
+    } else if conf.phases.is_empty() {
+        let stats = run_phase(&conf, &phases[0], &session, &program, &interrupt, None).await?;
+        (stats, Vec::new())
+    } else {
+        run_scenario(&conf, &phases, &session, &program, &interrupt).await?
     };
 
-    report::print_log_header();
-    let stats = par_execute(
-        "Running...",
-        &exec_options,
-        conf.sampling_interval,
-        runner,
-        interrupt.clone(),
-        !conf.quiet,
-    )
-    .await?;
-
     let stats_cmp = BenchmarkCmp {
         v1: &stats,
         v2: compare.as_ref().map(|c| &c.result),
     };
     println!();
     println!("{}", &stats_cmp);
+    if !phase_reports.is_empty() {
+        let phases_cmp = PhasesCmp {
+            v1: &phase_reports,
+            v2: compare.as_ref().map(|c| c.phases.as_slice()),
+        };
+        println!("{phases_cmp}");
+    }
+    if let Some(search) = &search_report {
+        println!();
+        println!("{}", report::fmt_search(search));
+    }
 
     let path = conf
         .output
         .clone()
         .unwrap_or_else(|| conf.default_output_file_name("json"));
 
-    let report = Report::new(conf, stats);
+    let traces = fetch_traces(&session, &conf.tracing, &stats).await;
+    let mut report = Report::new(conf, stats);
+    report.traces = traces;
+    report.phases = phase_reports;
+    report.search = search_report;
     match report.save(&path) {
         Ok(()) => {
             eprintln!("info: Saved report to {}", path.display());
@@ -303,9 +392,194 @@ async fn run(conf: RunCommand) -> Result<()> {
             exit(1);
         }
     }
+    if interrupt.is_interrupted_on_errors() {
+        exit(ERRORS_EXIT_CODE);
+    }
     Ok(())
 }
 
+/// Creates the workload invoking the functions of given phase
+fn phase_workload(
+    conf: &RunCommand,
+    session: &Context,
+    program: &Program,
+    phase: &PhaseConf,
+) -> Result<Workload> {
+    let functions = phase
+        .functions
+        .iter()
+        .map(|f| (FnRef::new(f.name.as_str()), f.weight))
+        .collect_vec();
+    Ok(Workload::new(
+        session.clone()?,
+        program.clone(),
+        &functions,
+        &conf.errors.tolerate,
+    ))
+}
+
+/// Runs a single measured phase of the benchmark.
+/// The phase options must be resolved, i.e. they must not contain unset values.
+async fn run_phase(
+    conf: &RunCommand,
+    phase: &PhaseConf,
+    session: &Context,
+    program: &Program,
+    interrupt: &Arc<InterruptHandler>,
+    timeline: Option<&mut Recorder>,
+) -> Result<BenchmarkStats> {
+    let exec_options = ExecutionOptions {
+        duration: phase.duration.unwrap(),
+        concurrency: phase.concurrency.unwrap(),
+        rate: phase.rate,
+        // Phases with their own rate run at a constant rate:
+        rate_profile: conf.rate_profile.clone().filter(|_| phase.rate.is_none()),
+        threads: conf.threads,
+        open_loop: conf.open_loop,
+        arrivals: conf.arrivals,
+        arrival_seed: conf.arrival_seed,
+        adaptive_concurrency: conf.adaptive_concurrency,
+        errors: conf.errors.clone(),
+    };
+    let label = if conf.phases.is_empty() {
+        "Running...".to_string()
+    } else {
+        format!("Running {}...", phase.name)
+    };
+    par_execute(
+        &label,
+        &exec_options,
+        conf.sampling_interval,
+        phase_workload(conf, session, program, phase)?,
+        interrupt.clone(),
+        !conf.quiet,
+        timeline,
+    )
+    .await
+}
+
+/// Runs the phases of a scenario one after another.
+/// Returns the statistics of the whole scenario, sampled on a single timeline,
+/// together with the statistics of each phase.
+/// Stops after the current phase if the run gets interrupted.
+async fn run_scenario(
+    conf: &RunCommand,
+    phases: &[PhaseConf],
+    session: &Context,
+    program: &Program,
+    interrupt: &Arc<InterruptHandler>,
+) -> Result<(BenchmarkStats, Vec<PhaseReport>)> {
+    let max_concurrency = phases.iter().filter_map(|p| p.concurrency).max().unwrap();
+    let mut timeline = Recorder::start(None, max_concurrency);
+    let mut results = Vec::with_capacity(phases.len());
+    for (i, phase) in phases.iter().enumerate() {
+        eprintln!("info: Running phase {}...", phase.name);
+        let stats = run_phase(
+            conf,
+            phase,
+            session,
+            program,
+            interrupt,
+            Some(&mut timeline),
+        )
+        .await?;
+        results.push((phase.clone(), stats));
+        if interrupt.is_interrupted() {
+            break;
+        }
+        if let Some(pause) = phase.pause.filter(|_| i + 1 < phases.len()) {
+            eprintln!("info: Pausing for {pause:?}...");
+            tokio::time::sleep(pause).await;
+        }
+    }
+    let stats = timeline.finish();
+    let phase_reports = results
+        .into_iter()
+        .map(|(phase, result)| PhaseReport::new(phase, result, stats.start_time))
+        .collect();
+    Ok((stats, phase_reports))
+}
+
+/// Searches for the highest rate meeting the response time objective, by running the phase
+/// repeatedly at different rates.
+/// Returns the statistics of the whole search, sampled on a single timeline,
+/// the statistics of each probed rate and the summary of the search.
+async fn search_rate(
+    conf: &RunCommand,
+    phase: &PhaseConf,
+    percentile: Percentile,
+    session: &Context,
+    program: &Program,
+    interrupt: &Arc<InterruptHandler>,
+) -> Result<(BenchmarkStats, Vec<PhaseReport>, SearchReport)> {
+    let mut search = RateSearch::new(&conf.search);
+    let mut timeline = Recorder::start(None, phase.concurrency.unwrap());
+    let mut results = Vec::new();
+    let mut probes = Vec::new();
+    while let Some(rate) = search.next_rate() {
+        let probe_phase = PhaseConf {
+            name: format!("probe {}", probes.len() + 1),
+            rate: Some(rate),
+            ..phase.clone()
+        };
+        eprintln!("info: Running {} at {rate:.0} op/s...", probe_phase.name);
+        let stats = run_phase(
+            conf,
+            &probe_phase,
+            session,
+            program,
+            interrupt,
+            Some(&mut timeline),
+        )
+        .await?;
+        if interrupt.is_interrupted() {
+            break;
+        }
+        let probe = Probe::new(&conf.search, percentile, rate, &stats);
+        match &probe.failure {
+            None => eprintln!("info: Objective met at {rate:.0} op/s"),
+            Some(failure) => eprintln!("info: Objective not met at {rate:.0} op/s: {failure}"),
+        }
+        search.record(rate, probe.passed());
+        results.push((probe_phase, stats));
+        probes.push(probe);
+    }
+    let stats = timeline.finish();
+    let phase_reports = results
+        .into_iter()
+        .map(|(phase, result)| PhaseReport::new(phase, result, stats.start_time))
+        .collect();
+    let search_report = SearchReport {
+        slo: conf.search.slo.unwrap(),
+        max_rate: search.highest_passed(),
+        probes,
+    };
+    Ok((stats, phase_reports, search_report))
+}
+
+/// Fetches the server-side traces of the slowest traced requests.
+/// Failures are reported as warnings, because the traces are not essential for the report.
+async fn fetch_traces(
+    session: &Context,
+    conf: &TracingConf,
+    stats: &BenchmarkStats,
+) -> Vec<RequestTrace> {
+    let mut requests = stats.traced_requests.clone();
+    trace::keep_slowest(&mut requests, conf.count);
+    if requests.is_empty() {
+        return Vec::new();
+    }
+    eprintln!("info: Fetching {} traces...", requests.len());
+    let mut traces = Vec::with_capacity(requests.len());
+    for request in &requests {
+        match session.fetch_trace(request).await {
+            Ok(trace) => traces.push(trace),
+            Err(e) => eprintln!("warning: Failed to fetch trace {}: {e}", request.tracing_id),
+        }
+    }
+    traces
+}
+
 async fn show(conf: ShowCommand) -> Result<()> {
     let report1 = load_report_or_abort(&conf.report);
     let report2 = conf.baseline.map(|p| load_report_or_abort(&p));
@@ -321,6 +595,21 @@ async fn show(conf: ShowCommand) -> Result<()> {
         v2: report2.as_ref().map(|r| &r.result),
     };
     println!("{results_cmp}");
+
+    if !report1.phases.is_empty() {
+        let phases_cmp = PhasesCmp {
+            v1: &report1.phases,
+            v2: report2.as_ref().map(|r| r.phases.as_slice()),
+        };
+        println!("{phases_cmp}");
+    }
+
+    if let Some(search) = &report1.search {
+        println!("{}", report::fmt_search(search));
+    }
+    if !report1.traces.is_empty() {
+        println!("{}", report::fmt_traces(&report1.traces));
+    }
     Ok(())
 }
 
@@ -371,6 +660,14 @@ async fn export_hdr_log(conf: HdrCommand) -> Result<()> {
             interval_duration,
             Tag::new(format!("{tag_prefix}requests").as_str()),
         )?;
+        if !sample.corrected_cycle_time_histogram_ns.0.is_empty() {
+            log_writer.write_histogram(
+                &sample.corrected_cycle_time_histogram_ns.0,
+                interval_start_time,
+                interval_duration,
+                Tag::new(format!("{tag_prefix}corrected-cycles").as_str()),
+            )?;
+        }
     }
     Ok(())
 }
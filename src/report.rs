@@ -5,7 +5,7 @@ use std::num::NonZeroUsize;
 use std::path::Path;
 use std::{fs, io};
 
-use chrono::{Local, NaiveDateTime, TimeZone};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
 use console::{pad_str, style, Alignment};
 use err_derive::*;
 use itertools::Itertools;
@@ -13,10 +13,13 @@ use serde::{Deserialize, Serialize};
 use statrs::statistics::Statistics;
 use strum::IntoEnumIterator;
 
-use crate::config::RunCommand;
+use crate::config::{Arrivals, ConnectionConf, PhaseConf, RunCommand};
+use crate::search::SearchReport;
 use crate::stats::{
-    BenchmarkCmp, BenchmarkStats, Bucket, Mean, Percentile, Sample, Significance, TimeDistribution,
+    BenchmarkCmp, BenchmarkStats, BreakdownStats, Bucket, ErrorCategory, Mean, Percentile, Sample,
+    Significance, TimeDistribution,
 };
+use crate::trace::RequestTrace;
 
 /// A standard error is multiplied by this factor to get the error margin.
 /// For a normally distributed random variable,
@@ -38,6 +41,37 @@ pub struct Report {
     pub conf: RunCommand,
     pub percentiles: Vec<f32>,
     pub result: BenchmarkStats,
+    /// Server-side traces of the slowest traced requests
+    #[serde(default)]
+    pub traces: Vec<RequestTrace>,
+    /// Results of the phases of a scenario run; empty if the run had a single phase.
+    /// In that case `result` covers the whole scenario.
+    #[serde(default)]
+    pub phases: Vec<PhaseReport>,
+    /// Results of the search of the maximum sustainable rate; the phases hold the probed runs
+    #[serde(default)]
+    pub search: Option<SearchReport>,
+}
+
+/// Configuration and results of a single phase of a scenario run
+#[derive(Serialize, Deserialize)]
+pub struct PhaseReport {
+    /// Phase configuration with the options inherited from the run command filled in
+    pub conf: PhaseConf,
+    /// Seconds since the start of the scenario
+    pub start_time_s: f64,
+    pub result: BenchmarkStats,
+}
+
+impl PhaseReport {
+    pub fn new(conf: PhaseConf, result: BenchmarkStats, scenario_start: DateTime<Local>) -> Self {
+        let start_time_s = (result.start_time - scenario_start).num_milliseconds() as f64 / 1000.0;
+        PhaseReport {
+            conf,
+            start_time_s,
+            result,
+        }
+    }
 }
 
 impl Report {
@@ -48,6 +82,9 @@ impl Report {
             conf,
             percentiles,
             result,
+            traces: Vec::new(),
+            phases: Vec::new(),
+            search: None,
         }
     }
     /// Loads benchmark results from a JSON file
@@ -376,6 +413,122 @@ where
 
 const REPORT_WIDTH: usize = 124;
 
+/// Compares the phases of two scenario runs.
+/// Phases are matched by name.
+pub struct PhasesCmp<'a> {
+    pub v1: &'a [PhaseReport],
+    pub v2: Option<&'a [PhaseReport]>,
+}
+
+impl<'a> PhasesCmp<'a> {
+    fn line<S, M, F>(
+        v1: &'a PhaseReport,
+        v2: Option<&'a PhaseReport>,
+        label: S,
+        unit: &str,
+        f: F,
+    ) -> Box<Line<M, &'a PhaseReport, F>>
+    where
+        S: ToString,
+        M: Display + Rational,
+        F: Fn(&PhaseReport) -> M,
+    {
+        Box::new(Line::new(label.to_string(), unit.to_string(), 0, v1, v2, f))
+    }
+
+    /// Formats the configuration and the main statistics of a single phase
+    fn fmt_phase(
+        f: &mut Formatter<'_>,
+        v1: &'a PhaseReport,
+        v2: Option<&'a PhaseReport>,
+    ) -> fmt::Result {
+        let cmp = BenchmarkCmp {
+            v1: &v1.result,
+            v2: v2.map(|p| &p.result),
+        };
+        writeln!(
+            f,
+            "{}",
+            fmt_section_header(&format!("PHASE {}", v1.conf.name))
+        )?;
+        if v2.is_some() {
+            writeln!(f, "{}", fmt_cmp_header(true))?;
+        }
+        let lines: Vec<Box<dyn Display>> = vec![
+            Self::line(v1, v2, "Functions", "", |p| {
+                p.conf.functions.iter().map(|f| f.to_string()).join(", ")
+            }),
+            Self::line(v1, v2, "Concurrency", "req", |p| {
+                Quantity::from(p.conf.concurrency)
+            }),
+            Self::line(v1, v2, "Max rate", "op/s", |p| Quantity::from(p.conf.rate)),
+            Self::line(v1, v2, "Start", "s", |p| {
+                Quantity::from(p.start_time_s).with_precision(1)
+            }),
+            cmp.line("Elapsed time", "s", |s| {
+                Quantity::from(s.elapsed_time_s).with_precision(3)
+            }),
+            cmp.line("Cycles", "op", |s| Quantity::from(s.cycle_count)),
+            cmp.line("Errors", "op", |s| Quantity::from(s.error_count)),
+            cmp.line("Throughput", "op/s", |s| Quantity::from(s.cycle_throughput))
+                .with_significance(cmp.cmp_cycle_throughput())
+                .with_orientation(1)
+                .into_box(),
+            cmp.line("Mean resp. time", "ms", |s| {
+                Quantity::from(s.resp_time_ms.as_ref().map(|rt| rt.mean)).with_precision(3)
+            })
+            .with_significance(cmp.cmp_mean_resp_time())
+            .with_orientation(-1)
+            .into_box(),
+        ];
+        for l in lines {
+            writeln!(f, "{l}")?;
+        }
+        for p in [Percentile::P50, Percentile::P99, Percentile::P99_9] {
+            let l = cmp
+                .line(p.name(), "ms", |s| {
+                    let rt = s.resp_time_ms.as_ref().map(|rt| rt.percentiles[p as usize]);
+                    Quantity::from(rt).with_precision(3)
+                })
+                .with_orientation(-1)
+                .with_significance(cmp.cmp_resp_time_percentile(p));
+            writeln!(f, "{l}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for PhasesCmp<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, v1) in self.v1.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let v2 = self
+                .v2
+                .and_then(|phases| phases.iter().find(|p| p.conf.name == v1.conf.name));
+            Self::fmt_phase(f, v1, v2)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the results of the search of the maximum sustainable rate as a report section
+pub fn fmt_search(search: &SearchReport) -> String {
+    format!("{}\n{}\n", fmt_section_header("SEARCH"), search)
+}
+
+/// Formats the traces of the slowest requests as a report section
+pub fn fmt_traces(traces: &[RequestTrace]) -> String {
+    let mut s = fmt_section_header("SLOWEST TRACED REQUESTS");
+    s.push('\n');
+    for t in traces {
+        s.push('\n');
+        s.push_str(&t.to_string());
+    }
+    s
+}
+
 fn fmt_section_header(name: &str) -> String {
     format!(
         "{} {}",
@@ -406,12 +559,26 @@ fn fmt_cmp_header(display_significance: bool) -> String {
     format!("{}", style(header).yellow().bold().for_stdout())
 }
 
+/// Formats the load balancing settings as a comma-separated list.
+fn fmt_load_balancing(conf: &ConnectionConf) -> String {
+    let mut settings = Vec::new();
+    if !conf.no_token_aware {
+        settings.push("token-aware".to_string());
+    }
+    if !conf.no_shard_aware {
+        settings.push("shard-aware port".to_string());
+    }
+    settings.extend(conf.datacenter.iter().map(|dc| format!("dc={dc}")));
+    settings.extend(conf.rack.iter().map(|rack| format!("rack={rack}")));
+    settings.join(", ")
+}
+
 pub struct RunConfigCmp<'a> {
     pub v1: &'a RunCommand,
     pub v2: Option<&'a RunCommand>,
 }
 
-impl RunConfigCmp<'_> {
+impl<'a> RunConfigCmp<'a> {
     fn line<S, M, F>(&self, label: S, unit: &str, f: F) -> Box<Line<M, &RunCommand, F>>
     where
         S: ToString,
@@ -446,6 +613,43 @@ impl RunConfigCmp<'_> {
         }
         keys
     }
+
+    /// Returns the set union of names of statements with custom execution options
+    /// in both configurations.
+    fn statement_names(&self) -> BTreeSet<&String> {
+        let mut keys = BTreeSet::new();
+        let with_options = |conf: &'a RunCommand| {
+            conf.statements
+                .iter()
+                .filter(|(_, s)| !s.options.is_empty())
+                .map(|(k, _)| k)
+        };
+        keys.extend(with_options(self.v1));
+        if let Some(v2) = self.v2 {
+            keys.extend(with_options(v2));
+        }
+        keys
+    }
+
+    /// Returns the set union of names of phases defined in both configurations.
+    fn phase_names(&self) -> BTreeSet<&String> {
+        let mut keys = BTreeSet::new();
+        keys.extend(self.v1.phases.iter().map(|p| &p.name));
+        if let Some(v2) = self.v2 {
+            keys.extend(v2.phases.iter().map(|p| &p.name));
+        }
+        keys
+    }
+
+    /// Returns the set union of names of execution profiles defined in both configurations.
+    fn profile_names(&self) -> BTreeSet<&String> {
+        let mut keys = BTreeSet::new();
+        keys.extend(self.v1.connection.profiles.iter().map(|p| &p.name));
+        if let Some(v2) = self.v2 {
+            keys.extend(v2.connection.profiles.iter().map(|p| &p.name));
+        }
+        keys
+    }
 }
 
 impl<'a> Display for RunConfigCmp<'a> {
@@ -489,6 +693,43 @@ impl<'a> Display for RunConfigCmp<'a> {
             writeln!(f, "{}", fmt_horizontal_line()).unwrap();
         }
 
+        let profile_names = self.profile_names();
+        if !profile_names.is_empty() {
+            for k in profile_names {
+                let label = format!("--profile {k}");
+                let line = self.line(label.as_str(), "", |conf| {
+                    let profile = conf.connection.profiles.iter().find(|p| &p.name == k);
+                    OptionDisplay(profile.map(|p| p.to_string()))
+                });
+                writeln!(f, "{line}").unwrap();
+            }
+            writeln!(f, "{}", fmt_horizontal_line()).unwrap();
+        }
+
+        let phase_names = self.phase_names();
+        if !phase_names.is_empty() {
+            for k in phase_names {
+                let label = format!("--phase {k}");
+                let line = self.line(label.as_str(), "", |conf| {
+                    let phase = conf.phases.iter().find(|p| &p.name == k);
+                    OptionDisplay(phase.map(|p| p.to_string()))
+                });
+                writeln!(f, "{line}").unwrap();
+            }
+            writeln!(f, "{}", fmt_horizontal_line()).unwrap();
+        }
+
+        let statement_names = self.statement_names();
+        if !statement_names.is_empty() {
+            for k in statement_names {
+                let line = self.line(k, "", |conf| {
+                    OptionDisplay(conf.statements.get(k).map(|s| s.options.to_string()))
+                });
+                writeln!(f, "{line}").unwrap();
+            }
+            writeln!(f, "{}", fmt_horizontal_line()).unwrap();
+        }
+
         let lines: Vec<Box<dyn Display>> = vec![
             self.line("Threads", "", |conf| Quantity::from(conf.threads)),
             self.line("Connections", "", |conf| {
@@ -498,6 +739,33 @@ impl<'a> Display for RunConfigCmp<'a> {
                 Quantity::from(conf.concurrency)
             }),
             self.line("Max rate", "op/s", |conf| Quantity::from(conf.rate)),
+            self.line("Rate profile", "", |conf| {
+                OptionDisplay(conf.rate_profile.as_ref().map(|p| p.to_string()))
+            }),
+            self.line("Open loop", "", |conf| conf.open_loop.to_string()),
+            self.line("Adaptive concurrency", "", |conf| {
+                OptionDisplay(conf.adaptive_concurrency.map(|slo| slo.to_string()))
+            }),
+            self.line("Arrivals", "", |conf| match conf.arrivals {
+                Arrivals::Fixed => conf.arrivals.to_string(),
+                arrivals => format!("{arrivals}, seed {}", conf.arrival_seed),
+            }),
+            self.line("Compression", "", |conf| {
+                OptionDisplay(conf.connection.compression.map(|c| c.to_string()))
+            }),
+            self.line("Load balancing", "", |conf| {
+                fmt_load_balancing(&conf.connection)
+            }),
+            self.line("Retry policy", "", |conf| conf.connection.retry.to_string()),
+            self.line("Speculative", "", |conf| {
+                OptionDisplay(conf.connection.speculative.map(|s| s.to_string()))
+            }),
+            self.line("Tracing", "", |conf| conf.tracing.to_string()),
+            self.line("Search", "", |conf| conf.search.to_string()),
+            self.line("Errors", "", |conf| conf.errors.to_string()),
+            self.line("Functions", "", |conf| {
+                conf.functions.iter().map(|f| f.to_string()).join(", ")
+            }),
             self.line("Warmup", "s", |conf| {
                 Quantity::from(conf.warmup_duration.seconds())
             }),
@@ -525,10 +793,17 @@ impl<'a> Display for RunConfigCmp<'a> {
     }
 }
 
-pub fn print_log_header() {
+/// Prints the header of the table of samples.
+/// The `adaptive_concurrency` flag adds the column with the concurrency limit of each sample.
+pub fn print_log_header(adaptive_concurrency: bool) {
+    let (limit_header, limit_unit) = if adaptive_concurrency {
+        ("    Limit", "    [req]")
+    } else {
+        ("", "")
+    };
     println!("{}", fmt_section_header("LOG"));
-    println!("{}", style("    Time  ───── Throughput ─────  ────────────────────────────────── Response times [ms] ───────────────────────────────────").yellow().bold().for_stdout());
-    println!("{}", style("     [s]      [op/s]     [req/s]         Min        25        50        75        90        95        99      99.9       Max").yellow().for_stdout());
+    println!("{}", style(format!("    Time  ───── Throughput ─────  ────────────────────────────────── Response times [ms] ───────────────────────────────────{limit_header}")).yellow().bold().for_stdout());
+    println!("{}", style(format!("     [s]      [op/s]     [req/s]         Min        25        50        75        90        95        99      99.9       Max{limit_unit}")).yellow().for_stdout());
 }
 
 impl Display for Sample {
@@ -548,7 +823,11 @@ impl Display for Sample {
             self.resp_time_percentiles[Percentile::P99 as usize],
             self.resp_time_percentiles[Percentile::P99_9 as usize],
             self.resp_time_percentiles[Percentile::Max as usize]
-        )
+        )?;
+        if let Some(limit) = self.concurrency_limit {
+            write!(f, " {limit:8}")?;
+        }
+        Ok(())
     }
 }
 
@@ -570,6 +849,139 @@ impl BenchmarkCmp<'_> {
     }
 }
 
+impl<'a> BenchmarkCmp<'a> {
+    /// Returns the keys of the statements executed in any of the compared runs
+    fn statement_keys(&self) -> BTreeSet<&'a str> {
+        let v1 = self.v1.statements.keys();
+        let v2 = self.v2.into_iter().flat_map(|s| s.statements.keys());
+        v1.chain(v2).map(|k| k.as_str()).collect()
+    }
+
+    /// Returns the categories of errors encountered in any of the compared runs
+    fn error_categories(&self) -> BTreeSet<ErrorCategory> {
+        let v1 = self.v1.error_stats.counts.keys();
+        let v2 = self
+            .v2
+            .into_iter()
+            .flat_map(|s| s.error_stats.counts.keys());
+        v1.chain(v2).copied().collect()
+    }
+
+    /// Returns the names of the functions called in any of the compared runs
+    fn function_names(&self) -> BTreeSet<&'a str> {
+        let v1 = self.v1.functions.keys();
+        let v2 = self.v2.into_iter().flat_map(|s| s.functions.keys());
+        v1.chain(v2).map(|k| k.as_str()).collect()
+    }
+
+    /// Formats the statistics of the cycles calling given function
+    fn fmt_function(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        writeln!(f, "{}", fmt_section_header(&format!("FUNCTION {name}")))?;
+        if self.v2.is_some() {
+            writeln!(f, "{}", fmt_cmp_header(true))?;
+        }
+        let cycles = self.line("Cycles", "op", |s| {
+            Quantity::from(s.functions.get(name).map(|f| f.cycle_count))
+        });
+        writeln!(f, "{cycles}")?;
+        self.fmt_breakdown_times(f, |s| s.functions.get(name), "op/s", "Mean cycle time")
+    }
+
+    /// Formats the statistics of the requests executed by given statement
+    fn fmt_statement(&self, f: &mut Formatter<'_>, key: &str) -> fmt::Result {
+        writeln!(f, "{}", fmt_section_header(&format!("STATEMENT {key}")))?;
+        if self.v2.is_some() {
+            writeln!(f, "{}", fmt_cmp_header(true))?;
+        }
+        let lines: Vec<Box<dyn Display>> = vec![
+            self.line("Requests", "req", |s| {
+                Quantity::from(s.statements.get(key).map(|st| st.request_count))
+            }),
+            self.line("Errors", "req", |s| {
+                Quantity::from(s.statements.get(key).map(|st| st.error_count))
+            }),
+            self.line("└─", "%", |s| {
+                Quantity::from(s.statements.get(key).and_then(|st| st.errors_ratio))
+                    .with_precision(1)
+            }),
+        ];
+        for l in lines {
+            writeln!(f, "{l}")?;
+        }
+        let error_categories: BTreeSet<ErrorCategory> = [Some(self.v1), self.v2]
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.statements.get(key))
+            .flat_map(|st| st.errors.keys().copied())
+            .collect();
+        for category in error_categories {
+            let l = self.line(format!("   {}", category.name()), "req", |s| {
+                let count = s.statements.get(key).map(|st| st.errors.get(&category));
+                Quantity::from(count.map(|c| c.copied().unwrap_or(0)))
+            });
+            writeln!(f, "{l}")?;
+        }
+        let lines: Vec<Box<dyn Display>> = vec![
+            self.line("Rows", "row", |s| {
+                Quantity::from(s.statements.get(key).map(|st| st.row_count))
+            }),
+            self.line("└─", "row/req", |s| {
+                Quantity::from(s.statements.get(key).and_then(|st| st.row_count_per_req))
+                    .with_precision(1)
+            }),
+        ];
+        for l in lines {
+            writeln!(f, "{l}")?;
+        }
+        self.fmt_breakdown_times(f, |s| s.statements.get(key), "req/s", "Mean resp. time")
+    }
+
+    /// Formats the throughput and the time distribution of a part of the workload,
+    /// e.g. of a statement or a function
+    fn fmt_breakdown_times<P: BreakdownStats>(
+        &self,
+        f: &mut Formatter<'_>,
+        part: impl Fn(&BenchmarkStats) -> Option<&P> + Copy,
+        throughput_unit: &str,
+        mean_time_label: &str,
+    ) -> fmt::Result {
+        let lines: Vec<Box<dyn Display>> = vec![
+            self.line("Throughput", throughput_unit, |s| {
+                Quantity::from(part(s).map(|p| p.throughput()))
+            })
+            .with_significance(self.cmp_breakdown_throughput(part))
+            .with_orientation(1)
+            .into_box(),
+            self.line(mean_time_label, "ms", |s| {
+                Quantity::from(part(s).map(|p| p.time_ms().mean)).with_precision(3)
+            })
+            .with_significance(self.cmp_breakdown_mean_time(part))
+            .with_orientation(-1)
+            .into_box(),
+        ];
+        for l in lines {
+            writeln!(f, "{l}")?;
+        }
+        for p in [
+            Percentile::P50,
+            Percentile::P90,
+            Percentile::P99,
+            Percentile::P99_9,
+            Percentile::Max,
+        ] {
+            let l = self
+                .line(p.name(), "ms", |s| {
+                    let t = part(s).map(|b| b.time_ms().percentiles[p as usize]);
+                    Quantity::from(t).with_precision(3)
+                })
+                .with_orientation(-1)
+                .with_significance(self.cmp_breakdown_time_percentile(part, p));
+            writeln!(f, "{l}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Formats all benchmark stats
 impl<'a> Display for BenchmarkCmp<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -578,7 +990,7 @@ impl<'a> Display for BenchmarkCmp<'a> {
             writeln!(f, "{}", fmt_cmp_header(true))?;
         }
 
-        let summary: Vec<Box<dyn Display>> = vec![
+        let mut summary: Vec<Box<dyn Display>> = vec![
             self.line("Elapsed time", "s", |s| {
                 Quantity::from(s.elapsed_time_s).with_precision(3)
             }),
@@ -601,6 +1013,41 @@ impl<'a> Display for BenchmarkCmp<'a> {
             self.line("└─", "row/req", |s| {
                 Quantity::from(s.row_count_per_req).with_precision(1)
             }),
+        ];
+        if self.v1.batch_count > 0 || self.v2.map(|s| s.batch_count > 0).unwrap_or(false) {
+            summary.push(self.line("Batches", "req", |s| Quantity::from(s.batch_count)));
+            summary.push(self.line("└─", "stmt/req", |s| {
+                Quantity::from(s.statements_per_batch).with_precision(1)
+            }));
+        }
+        if self.v1.retry_count > 0 || self.v2.map(|s| s.retry_count > 0).unwrap_or(false) {
+            summary.push(self.line("Retries", "req", |s| Quantity::from(s.retry_count)));
+            summary.push(self.line("└─", "retry/req", |s| {
+                Quantity::from(s.retries_per_req).with_precision(3)
+            }));
+        }
+        let lwt_count = |s: &BenchmarkStats| s.lwt_applied_count + s.lwt_rejected_count;
+        if lwt_count(self.v1) > 0 || self.v2.map(|s| lwt_count(s) > 0).unwrap_or(false) {
+            summary.push(self.line("LWT applied", "req", |s| {
+                Quantity::from(s.lwt_applied_count)
+            }));
+            summary.push(self.line("LWT not applied", "req", |s| {
+                Quantity::from(s.lwt_rejected_count)
+            }));
+            summary.push(self.line("└─", "%", |s| {
+                Quantity::from(s.lwt_rejected_ratio).with_precision(1)
+            }));
+        }
+        let launch_delays = |s: &BenchmarkStats| s.late_launch_count + s.dropped_launch_count;
+        if launch_delays(self.v1) > 0 || self.v2.map(|s| launch_delays(s) > 0).unwrap_or(false) {
+            summary.push(self.line("Late launches", "op", |s| {
+                Quantity::from(s.late_launch_count)
+            }));
+            summary.push(self.line("Dropped launches", "op", |s| {
+                Quantity::from(s.dropped_launch_count)
+            }));
+        }
+        let rest: Vec<Box<dyn Display>> = vec![
             self.line("Samples", "", |s| Quantity::from(s.log.len())),
             self.line("Mean sample size", "op", |s| {
                 Quantity::from(s.log.iter().map(|s| s.cycle_count as f64).mean())
@@ -610,6 +1057,9 @@ impl<'a> Display for BenchmarkCmp<'a> {
             }),
             self.line("Concurrency", "req", |s| Quantity::from(s.concurrency)),
             self.line("└─", "%", |s| Quantity::from(s.concurrency_ratio)),
+            self.line("Concurrency limit", "req", |s| {
+                Quantity::from(s.concurrency_limit)
+            }),
             self.line("Throughput", "op/s", |s| Quantity::from(s.cycle_throughput))
                 .with_significance(self.cmp_cycle_throughput())
                 .with_orientation(1)
@@ -635,6 +1085,7 @@ impl<'a> Display for BenchmarkCmp<'a> {
             .with_orientation(-1)
             .into_box(),
         ];
+        summary.extend(rest);
 
         for l in summary {
             writeln!(f, "{l}")?;
@@ -674,6 +1125,14 @@ impl<'a> Display for BenchmarkCmp<'a> {
                     .with_significance(self.cmp_resp_time_percentile(*p));
                 writeln!(f, "{l}")?;
             }
+            if self.v1.speculative_count > 0
+                || self.v2.map(|s| s.speculative_count > 0).unwrap_or(false)
+            {
+                let l = self.line("Speculative", "%", |s| {
+                    Quantity::from(s.speculative_ratio).with_precision(2)
+                });
+                writeln!(f, "{l}")?;
+            }
 
             writeln!(f)?;
             writeln!(f, "{}", fmt_section_header("RESPONSE TIME DISTRIBUTION"))?;
@@ -705,11 +1164,82 @@ impl<'a> Display for BenchmarkCmp<'a> {
             }
         }
 
-        if self.v1.error_count > 0 {
+        if self.v1.corrected_cycle_time_ms.is_some() {
+            writeln!(f)?;
+            writeln!(f, "{}", fmt_section_header("CORRECTED CYCLE TIMES [ms]"))?;
+            if self.v2.is_some() {
+                writeln!(f, "{}", fmt_cmp_header(true))?;
+            }
+            let mean = self.line("Mean", "", |s| {
+                let t = s.corrected_cycle_time_ms.as_ref().map(|t| t.mean);
+                Quantity::from(t).with_precision(3)
+            });
+            writeln!(f, "{mean}")?;
+            for p in [
+                Percentile::P50,
+                Percentile::P90,
+                Percentile::P99,
+                Percentile::P99_9,
+                Percentile::P99_99,
+                Percentile::Max,
+            ] {
+                let l = self
+                    .line(p.name(), "", |s| {
+                        let t = s
+                            .corrected_cycle_time_ms
+                            .as_ref()
+                            .map(|t| t.percentiles[p as usize]);
+                        Quantity::from(t).with_precision(3)
+                    })
+                    .with_orientation(-1)
+                    .with_significance(self.cmp_corrected_cycle_time_percentile(p));
+                writeln!(f, "{l}")?;
+            }
+        }
+
+        // A breakdown is pointless if all cycles call the same function:
+        let function_names = self.function_names();
+        if function_names.len() > 1 {
+            for name in function_names {
+                writeln!(f)?;
+                self.fmt_function(f, name)?;
+            }
+        }
+
+        // A breakdown is pointless if all requests come from a single statement:
+        let statement_keys = self.statement_keys();
+        if statement_keys.len() > 1 {
+            for key in statement_keys {
+                writeln!(f)?;
+                self.fmt_statement(f, key)?;
+            }
+        }
+
+        let error_categories = self.error_categories();
+        if !error_categories.is_empty() {
             writeln!(f)?;
             writeln!(f, "{}", fmt_section_header("ERRORS"))?;
-            for e in self.v1.errors.iter() {
-                writeln!(f, "{e}")?;
+            if self.v2.is_some() {
+                writeln!(f, "{}", fmt_cmp_header(true))?;
+            }
+            for category in error_categories.iter() {
+                let l = self.line(category.name(), "req", |s| {
+                    Quantity::from(s.error_stats.counts.get(category).copied().unwrap_or(0))
+                });
+                writeln!(f, "{l}")?;
+            }
+            for (category, examples) in self.v1.error_stats.examples.iter() {
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "{}",
+                    style(format!("Examples of {} errors:", category.name()))
+                        .yellow()
+                        .for_stdout()
+                )?;
+                for e in examples {
+                    writeln!(f, "{e}")?;
+                }
             }
         }
         Ok(())
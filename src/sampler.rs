@@ -1,5 +1,6 @@
 use crate::error::Result;
-use crate::{config, Interval, Workload, WorkloadStats};
+use crate::workload::CycleExecutor;
+use crate::{config, Interval, WorkloadStats};
 use futures::channel::mpsc::Sender;
 use futures::SinkExt;
 use std::time::Instant;
@@ -7,23 +8,23 @@ use std::time::Instant;
 /// Responsible for periodically getting a snapshot of statistics from the `workload`
 /// and sending them to the `output` channel. The sampling period is controlled by `sampling`.
 /// Snapshot is not taken near the end of the run to avoid small final sample.
-pub struct Sampler<'a> {
+pub struct Sampler<'a, W> {
     run_duration: config::Interval,
     sampling: config::Interval,
-    workload: &'a Workload,
+    workload: &'a W,
     output: &'a mut Sender<Result<WorkloadStats>>,
     start_time: Instant,
     last_snapshot_time: Instant,
     last_snapshot_cycle: u64,
 }
 
-impl<'a> Sampler<'a> {
+impl<'a, W: CycleExecutor> Sampler<'a, W> {
     pub fn new(
         run_duration: config::Interval,
         sampling: config::Interval,
-        workload: &'a Workload,
+        workload: &'a W,
         output: &'a mut Sender<Result<WorkloadStats>>,
-    ) -> Sampler<'a> {
+    ) -> Sampler<'a, W> {
         let start_time = Instant::now();
         Sampler {
             run_duration,
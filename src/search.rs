@@ -0,0 +1,228 @@
+//! Search of the highest rate meeting a response time objective
+
+use std::fmt::{Display, Formatter};
+
+use console::style;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::config::{LatencySlo, SearchConf};
+use crate::stats::{BenchmarkStats, Percentile};
+
+/// A run is considered not sustainable if it doesn't reach this fraction of the target rate
+const MIN_THROUGHPUT_RATIO: f64 = 0.95;
+
+/// Returns the reported percentile matching the percentile of the objective
+pub fn find_percentile(slo: &LatencySlo) -> Option<Percentile> {
+    Percentile::iter().find(|p| (p.value() - slo.percentile).abs() < 1e-9)
+}
+
+/// Chooses the rates to probe.
+/// Starts from the minimum rate and doubles it until a probe fails or the maximum rate is reached.
+/// Then bisects the range between the highest passing and the lowest failing rate.
+pub struct RateSearch {
+    min_rate: f64,
+    max_rate: Option<f64>,
+    precision: f64,
+    highest_passed: Option<f64>,
+    lowest_failed: Option<f64>,
+}
+
+impl RateSearch {
+    pub fn new(conf: &SearchConf) -> RateSearch {
+        RateSearch {
+            min_rate: conf.min_rate,
+            max_rate: conf.max_rate,
+            precision: conf.precision / 100.0,
+            highest_passed: None,
+            lowest_failed: None,
+        }
+    }
+
+    /// Returns the rate to probe next or `None` if the search is finished
+    pub fn next_rate(&self) -> Option<f64> {
+        match (self.highest_passed, self.lowest_failed) {
+            (None, None) => Some(self.min_rate),
+            (None, Some(_)) => None,
+            (Some(passed), None) => match self.max_rate {
+                Some(max_rate) if passed >= max_rate => None,
+                Some(max_rate) => Some((2.0 * passed).min(max_rate)),
+                None => Some(2.0 * passed),
+            },
+            (Some(passed), Some(failed)) if failed <= passed * (1.0 + self.precision) => None,
+            (Some(passed), Some(failed)) => Some((passed + failed) / 2.0),
+        }
+    }
+
+    /// Records the result of probing given rate
+    pub fn record(&mut self, rate: f64, passed: bool) {
+        if passed {
+            self.highest_passed = Some(self.highest_passed.map_or(rate, |r| r.max(rate)));
+        } else {
+            self.lowest_failed = Some(self.lowest_failed.map_or(rate, |r| r.min(rate)));
+        }
+    }
+
+    /// Returns the highest rate that met the objective
+    pub fn highest_passed(&self) -> Option<f64> {
+        self.highest_passed
+    }
+}
+
+/// Result of a single run of the search
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Probe {
+    /// Target rate of cycles
+    pub rate: f64,
+    /// Achieved rate of cycles
+    pub throughput: f64,
+    /// Response time at the percentile of the objective
+    pub resp_time_ms: Option<f64>,
+    pub errors_ratio: Option<f64>,
+    /// Reason of not meeting the objective; `None` if the objective was met
+    pub failure: Option<String>,
+}
+
+impl Probe {
+    /// Checks the statistics of a run at given rate against the objective
+    pub fn new(
+        conf: &SearchConf,
+        percentile: Percentile,
+        rate: f64,
+        stats: &BenchmarkStats,
+    ) -> Probe {
+        let throughput = stats.cycle_throughput.value;
+        let resp_time_ms = stats
+            .resp_time_ms
+            .as_ref()
+            .map(|rt| rt.percentiles[percentile as usize].value);
+        let errors_ratio = stats.errors_ratio;
+        let max_resp_time_ms = conf
+            .slo
+            .map(|slo| slo.max_resp_time.as_secs_f64() * 1000.0)
+            .unwrap_or(f64::INFINITY);
+
+        let failure = if throughput < MIN_THROUGHPUT_RATIO * rate {
+            Some(format!("throughput {throughput:.0} op/s below target"))
+        } else if errors_ratio.is_some_and(|r| r > conf.max_error_ratio) {
+            Some(format!("{:.2}% errors", errors_ratio.unwrap()))
+        } else if resp_time_ms.is_some_and(|t| t > max_resp_time_ms) {
+            Some(format!("response time {:.3} ms", resp_time_ms.unwrap()))
+        } else {
+            None
+        };
+        Probe {
+            rate,
+            throughput,
+            resp_time_ms,
+            errors_ratio,
+            failure,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Results of the search saved in the report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchReport {
+    pub slo: LatencySlo,
+    /// The highest probed rate meeting the objective
+    pub max_rate: Option<f64>,
+    pub probes: Vec<Probe>,
+}
+
+impl Display for SearchReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            style(format!(
+                "{:>5}  {:>12}  {:>12}  {:>12}  {:>10}  Result",
+                "Probe", "Rate", "Throughput", self.slo, "Errors"
+            ))
+            .yellow()
+            .bold()
+            .for_stdout()
+        )?;
+        writeln!(
+            f,
+            "{}",
+            style(format!(
+                "{:>5}  {:>12}  {:>12}  {:>12}  {:>10}",
+                "", "[op/s]", "[op/s]", "[ms]", "[%]"
+            ))
+            .yellow()
+            .for_stdout()
+        )?;
+        for (i, p) in self.probes.iter().enumerate() {
+            let result = match &p.failure {
+                None => style("passed".to_string()).green(),
+                Some(failure) => style(format!("failed: {failure}")).red(),
+            };
+            writeln!(
+                f,
+                "{:>5}  {:>12.0}  {:>12.0}  {:>12}  {:>10}  {}",
+                i + 1,
+                p.rate,
+                p.throughput,
+                p.resp_time_ms
+                    .map(|t| format!("{t:.3}"))
+                    .unwrap_or_default(),
+                p.errors_ratio
+                    .map(|r| format!("{r:.2}"))
+                    .unwrap_or_default(),
+                result.for_stdout()
+            )?;
+        }
+        writeln!(f)?;
+        match self.max_rate {
+            Some(rate) => write!(f, "Maximum sustainable rate: {rate:.0} op/s"),
+            None => write!(f, "No probed rate met the objective"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::SearchConf;
+    use crate::search::RateSearch;
+
+    #[test]
+    fn doubles_rate_and_then_bisects() {
+        let conf = SearchConf {
+            min_rate: 1000.0,
+            precision: 10.0,
+            ..Default::default()
+        };
+        let mut search = RateSearch::new(&conf);
+        let mut probed = Vec::new();
+        while let Some(rate) = search.next_rate() {
+            probed.push(rate);
+            search.record(rate, rate <= 5000.0);
+        }
+        assert_eq!(
+            probed,
+            vec![1000.0, 2000.0, 4000.0, 8000.0, 6000.0, 5000.0, 5500.0]
+        );
+        assert_eq!(search.highest_passed(), Some(5000.0));
+    }
+
+    #[test]
+    fn stops_at_max_rate() {
+        let conf = SearchConf {
+            min_rate: 1000.0,
+            max_rate: Some(3000.0),
+            ..Default::default()
+        };
+        let mut search = RateSearch::new(&conf);
+        let mut probed = Vec::new();
+        while let Some(rate) = search.next_rate() {
+            probed.push(rate);
+            search.record(rate, true);
+        }
+        assert_eq!(probed, vec![1000.0, 2000.0, 3000.0]);
+    }
+}
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Local};
 use std::cmp::min;
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 use std::time::{Instant, SystemTime};
 
@@ -12,8 +12,10 @@ use strum::EnumCount;
 use strum::IntoEnumIterator;
 use strum_macros::{EnumCount as EnumCountM, EnumIter};
 
+use crate::context::StatementStats;
 use crate::histogram::SerializableHistogram;
-use crate::workload::WorkloadStats;
+use crate::trace::{self, TracedRequest};
+use crate::workload::{FnStats, WorkloadStats};
 
 /// Controls the maximum order of autocovariance taken into
 /// account when estimating the long run mean error. Higher values make the estimator
@@ -194,7 +196,110 @@ fn not_nan_f32(x: f32) -> Option<f32> {
     }
 }
 
-const MAX_KEPT_ERRORS: usize = 10;
+/// Maximum number of distinct error messages kept for each error category
+const MAX_ERROR_EXAMPLES: usize = 3;
+
+/// Cause of a failed request
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request or the replicas timed out
+    Timeout,
+    /// The coordinator was overloaded
+    Overloaded,
+    /// Not enough replicas were alive or the node was bootstrapping
+    Unavailable,
+    /// Replicas failed to read the data
+    ReadFailure,
+    /// Replicas failed to write the data
+    WriteFailure,
+    /// The request was rejected, e.g. because of a syntax error, missing permissions
+    /// or a statement unknown to the node
+    InvalidRequest,
+    /// The connection to the node was broken or couldn't serve more requests
+    Connection,
+    /// Any other error
+    Other,
+}
+
+impl ErrorCategory {
+    /// Returns true if the error is likely temporary, i.e. the request may succeed when retried
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ErrorCategory::Timeout
+                | ErrorCategory::Overloaded
+                | ErrorCategory::Unavailable
+                | ErrorCategory::Connection
+        )
+    }
+
+    /// Returns true if the error signals that the cluster can't keep up with the load
+    pub fn is_overload(&self) -> bool {
+        matches!(self, ErrorCategory::Timeout | ErrorCategory::Overloaded)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Overloaded => "overloaded",
+            ErrorCategory::Unavailable => "unavailable",
+            ErrorCategory::ReadFailure => "read failure",
+            ErrorCategory::WriteFailure => "write failure",
+            ErrorCategory::InvalidRequest => "invalid request",
+            ErrorCategory::Connection => "connection",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+/// Counts failed requests by the category of the error.
+/// Keeps a few example error messages of each category.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ErrorStats {
+    pub counts: BTreeMap<ErrorCategory, u64>,
+    pub examples: BTreeMap<ErrorCategory, Vec<String>>,
+}
+
+impl ErrorStats {
+    /// Records a failed request.
+    /// The `message` is called only if the message may be kept as an example.
+    pub fn record(&mut self, category: ErrorCategory, message: impl FnOnce() -> String) {
+        *self.counts.entry(category).or_default() += 1;
+        let examples = self.examples.entry(category).or_default();
+        if examples.len() < MAX_ERROR_EXAMPLES {
+            let message = message();
+            if !examples.contains(&message) {
+                examples.push(message);
+            }
+        }
+    }
+
+    /// Adds the counts and the examples recorded by another instance
+    pub fn add(&mut self, other: &ErrorStats) {
+        for (category, count) in other.counts.iter() {
+            *self.counts.entry(*category).or_default() += count;
+        }
+        for (category, messages) in other.examples.iter() {
+            let examples = self.examples.entry(*category).or_default();
+            for message in messages {
+                if examples.len() >= MAX_ERROR_EXAMPLES {
+                    break;
+                }
+                if !examples.contains(message) {
+                    examples.push(message.clone());
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+/// Maximum number of traced requests collected during the run
+const MAX_KEPT_TRACES: usize = 1000;
 
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, EnumIter, EnumCountM)]
@@ -266,8 +371,22 @@ pub struct Sample {
     pub cycle_count: u64,
     pub request_count: u64,
     pub error_count: u64,
-    pub errors: HashSet<String>,
+    /// Failed requests by the category of the error
+    #[serde(default)]
+    pub error_stats: ErrorStats,
+    #[serde(default)]
+    pub retry_count: u64,
+    #[serde(default)]
+    pub speculative_count: u64,
+    #[serde(default)]
+    pub lwt_applied_count: u64,
+    #[serde(default)]
+    pub lwt_rejected_count: u64,
     pub row_count: u64,
+    #[serde(default)]
+    pub batch_count: u64,
+    #[serde(default)]
+    pub batch_statement_count: u64,
     pub mean_queue_len: f32,
     pub cycle_throughput: f32,
     pub req_throughput: f32,
@@ -278,45 +397,208 @@ pub struct Sample {
     pub resp_time_percentiles: [f32; Percentile::COUNT],
     pub cycle_time_histogram_ns: SerializableHistogram,
     pub resp_time_histogram_ns: SerializableHistogram,
+    /// Distribution of the number of attempts needed to complete a request
+    #[serde(default)]
+    pub attempts_histogram: SerializableHistogram,
+    /// Statistics of requests broken down by the statement
+    #[serde(default)]
+    pub statements: BTreeMap<String, StatementSample>,
+    /// Statistics of cycles broken down by the workload function
+    #[serde(default)]
+    pub functions: BTreeMap<String, FunctionSample>,
+    /// Number of cycles started on a schedule, e.g. when the rate was limited
+    #[serde(default)]
+    pub corrected_cycle_count: u64,
+    /// Cycle times measured from the scheduled start time instead of the actual start time.
+    /// Unlike the regular cycle times, they include the time the cycles waited for being started
+    /// when the client couldn't keep up with the schedule, so they are not subject
+    /// to coordinated omission.
+    #[serde(default)]
+    pub mean_corrected_cycle_time_ms: f32,
+    #[serde(default)]
+    pub corrected_cycle_time_percentiles: [f32; Percentile::COUNT],
+    #[serde(default)]
+    pub corrected_cycle_time_histogram_ns: SerializableHistogram,
+    /// Number of cycles started noticeably later than scheduled
+    #[serde(default)]
+    pub late_launch_count: u64,
+    /// Number of scheduled cycles skipped in the open-loop mode because too many were in progress
+    #[serde(default)]
+    pub dropped_launch_count: u64,
+    /// Limit of concurrent requests per thread set by the adaptive concurrency controller
+    #[serde(default)]
+    pub concurrency_limit: Option<usize>,
+}
+
+/// Records basic statistics of the calls of a single workload function within a sample
+#[derive(Serialize, Deserialize)]
+pub struct FunctionSample {
+    pub cycle_count: u64,
+    pub cycle_throughput: f32,
+    pub mean_cycle_time_ms: f32,
+    pub cycle_time_percentiles: [f32; Percentile::COUNT],
+}
+
+/// Records basic statistics of requests executed by a single statement within a sample
+#[derive(Serialize, Deserialize)]
+pub struct StatementSample {
+    pub request_count: u64,
+    pub error_count: u64,
+    pub row_count: u64,
+    pub req_throughput: f32,
+    pub mean_resp_time_ms: f32,
+    pub resp_time_percentiles: [f32; Percentile::COUNT],
+    /// Counts of failed requests by the category of the error
+    #[serde(default)]
+    pub errors: BTreeMap<ErrorCategory, u64>,
+}
+
+/// Statistics of a part of the workload within a sample, e.g. of a single statement or function
+trait BreakdownSample {
+    /// Returns the number of requests or cycles
+    fn count(&self) -> u64;
+    fn throughput(&self) -> f32;
+    fn mean_time_ms(&self) -> f32;
+    fn time_percentiles(&self) -> &[f32; Percentile::COUNT];
+}
+
+impl BreakdownSample for FunctionSample {
+    fn count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    fn throughput(&self) -> f32 {
+        self.cycle_throughput
+    }
+
+    fn mean_time_ms(&self) -> f32 {
+        self.mean_cycle_time_ms
+    }
+
+    fn time_percentiles(&self) -> &[f32; Percentile::COUNT] {
+        &self.cycle_time_percentiles
+    }
+}
+
+impl BreakdownSample for StatementSample {
+    fn count(&self) -> u64 {
+        self.request_count
+    }
+
+    fn throughput(&self) -> f32 {
+        self.req_throughput
+    }
+
+    fn mean_time_ms(&self) -> f32 {
+        self.mean_resp_time_ms
+    }
+
+    fn time_percentiles(&self) -> &[f32; Percentile::COUNT] {
+        &self.resp_time_percentiles
+    }
 }
 
 impl Sample {
     pub fn new(base_start_time: Instant, stats: &[WorkloadStats]) -> Sample {
         assert!(!stats.is_empty());
         let mut cycle_count = 0;
+        let mut late_launch_count = 0;
+        let mut dropped_launch_count = 0;
         let mut cycle_times_ns = Histogram::new(3).unwrap();
+        let mut corrected_cycle_times_ns = Histogram::new(3).unwrap();
 
         let mut request_count = 0;
         let mut row_count = 0;
-        let mut errors = HashSet::new();
+        let mut batch_count = 0;
+        let mut batch_statement_count = 0;
+        let mut error_stats = ErrorStats::default();
         let mut error_count = 0;
+        let mut retry_count = 0;
+        let mut speculative_count = 0;
+        let mut lwt_applied_count = 0;
+        let mut lwt_rejected_count = 0;
         let mut mean_queue_len = 0.0;
         let mut duration_s = 0.0;
         let mut resp_times_ns = Histogram::new(3).unwrap();
 
         let mut cycle_time_histogram_ns = Histogram::new(3).unwrap();
         let mut resp_time_histogram_ns = Histogram::new(3).unwrap();
+        let mut attempts_histogram = Histogram::new(3).unwrap();
+        let mut statements: BTreeMap<&str, StatementStats> = BTreeMap::new();
+        let mut functions: BTreeMap<&str, FnStats> = BTreeMap::new();
 
         for s in stats {
             let ss = &s.session_stats;
             let fs = &s.function_stats;
             request_count += ss.req_count;
             row_count += ss.row_count;
-            if errors.len() < MAX_KEPT_ERRORS {
-                errors.extend(ss.req_errors.iter().cloned());
-            }
+            batch_count += ss.batch_count;
+            batch_statement_count += ss.batch_statement_count;
+            error_stats.add(&ss.req_errors);
             error_count += ss.req_error_count;
+            retry_count += ss.retry_count;
+            speculative_count += ss.speculative_count;
+            lwt_applied_count += ss.lwt_applied_count;
+            lwt_rejected_count += ss.lwt_rejected_count;
             mean_queue_len += ss.mean_queue_length / stats.len() as f32;
             duration_s += (s.end_time - s.start_time).as_secs_f32() / stats.len() as f32;
             resp_times_ns.add(&ss.resp_times_ns).unwrap();
             resp_time_histogram_ns.add(&ss.resp_times_ns).unwrap();
+            attempts_histogram.add(&ss.attempts).unwrap();
+            for (key, st) in ss.statements.iter() {
+                let acc = statements.entry(key.as_str()).or_default();
+                acc.req_count += st.req_count;
+                acc.req_error_count += st.req_error_count;
+                acc.row_count += st.row_count;
+                acc.resp_times_ns.add(&st.resp_times_ns).unwrap();
+                for (category, count) in st.errors.iter() {
+                    *acc.errors.entry(*category).or_default() += count;
+                }
+            }
 
             cycle_count += fs.call_count;
+            late_launch_count += fs.late_launch_count;
+            dropped_launch_count += fs.dropped_launch_count;
+            for (name, fs) in s.functions.iter() {
+                let acc = functions.entry(name.as_str()).or_default();
+                acc.call_count += fs.call_count;
+                acc.call_times_ns.add(&fs.call_times_ns).unwrap();
+            }
             cycle_times_ns.add(&fs.call_times_ns).unwrap();
             cycle_time_histogram_ns.add(&fs.call_times_ns).unwrap();
+            corrected_cycle_times_ns
+                .add(&fs.corrected_call_times_ns)
+                .unwrap();
         }
         let resp_time_percentiles = percentiles_ms(&resp_times_ns);
         let call_time_percentiles = percentiles_ms(&cycle_times_ns);
+        let statements = statements
+            .into_iter()
+            .map(|(key, st)| {
+                let sample = StatementSample {
+                    request_count: st.req_count,
+                    error_count: st.req_error_count,
+                    row_count: st.row_count,
+                    req_throughput: st.req_count as f32 / duration_s,
+                    mean_resp_time_ms: st.resp_times_ns.mean() as f32 / 1000000.0,
+                    resp_time_percentiles: percentiles_ms(&st.resp_times_ns),
+                    errors: st.errors,
+                };
+                (key.to_string(), sample)
+            })
+            .collect();
+        let functions = functions
+            .into_iter()
+            .map(|(name, fs)| {
+                let sample = FunctionSample {
+                    cycle_count: fs.call_count,
+                    cycle_throughput: fs.call_count as f32 / duration_s,
+                    mean_cycle_time_ms: fs.call_times_ns.mean() as f32 / 1000000.0,
+                    cycle_time_percentiles: percentiles_ms(&fs.call_times_ns),
+                };
+                (name.to_string(), sample)
+            })
+            .collect();
 
         Sample {
             time_s: (stats[0].start_time - base_start_time).as_secs_f32(),
@@ -324,8 +606,14 @@ impl Sample {
             cycle_count,
             request_count,
             row_count,
+            batch_count,
+            batch_statement_count,
             error_count,
-            errors,
+            error_stats,
+            retry_count,
+            speculative_count,
+            lwt_applied_count,
+            lwt_rejected_count,
             mean_queue_len: not_nan_f32(mean_queue_len).unwrap_or(0.0),
             cycle_throughput: cycle_count as f32 / duration_s,
             req_throughput: request_count as f32 / duration_s,
@@ -336,6 +624,19 @@ impl Sample {
             mean_resp_time_ms: resp_times_ns.mean() as f32 / 1000000.0,
             resp_time_percentiles,
             resp_time_histogram_ns: SerializableHistogram(resp_time_histogram_ns),
+            attempts_histogram: SerializableHistogram(attempts_histogram),
+            statements,
+            functions,
+            corrected_cycle_count: corrected_cycle_times_ns.len(),
+            mean_corrected_cycle_time_ms: not_nan_f32(
+                corrected_cycle_times_ns.mean() as f32 / 1000000.0,
+            )
+            .unwrap_or(0.0),
+            corrected_cycle_time_percentiles: percentiles_ms(&corrected_cycle_times_ns),
+            corrected_cycle_time_histogram_ns: SerializableHistogram(corrected_cycle_times_ns),
+            late_launch_count,
+            dropped_launch_count,
+            concurrency_limit: None,
         }
     }
 }
@@ -402,6 +703,32 @@ impl Log {
         Mean::compute(t.as_slice(), w.as_slice())
     }
 
+    /// Computes the throughput and the time distribution of a part of the workload,
+    /// e.g. of a single statement or function, from its statistics in each sample.
+    /// The `part` accessor returns `None` for the samples in which the part wasn't executed;
+    /// such samples are included with zero throughput.
+    fn breakdown_stats<P: BreakdownSample>(
+        &self,
+        part: impl Fn(&Sample) -> Option<&P>,
+        times_ns: &Histogram<u64>,
+    ) -> (Mean, TimeDistribution) {
+        let parts: Vec<Option<&P>> = self.samples.iter().map(part).collect();
+        let values = |f: &dyn Fn(&P) -> f32| -> Vec<f32> {
+            parts.iter().map(|p| p.map(f).unwrap_or(0.0)).collect()
+        };
+        let durations: Vec<f32> = self.samples.iter().map(|s| s.duration_s).collect();
+        let weights = values(&|p| p.count() as f32);
+        let throughput = Mean::compute(&values(&|p| p.throughput()), &durations);
+        let time_ms = TimeDistribution {
+            mean: Mean::compute(&values(&|p| p.mean_time_ms()), &weights),
+            percentiles: Percentile::iter()
+                .map(|pc| Mean::compute(&values(&|p| p.time_percentiles()[pc as usize]), &weights))
+                .collect(),
+            distribution: distribution(times_ns),
+        };
+        (throughput, time_ms)
+    }
+
     fn cycle_time_ms(&self) -> Mean {
         let t: Vec<f32> = self.samples.iter().map(|s| s.mean_cycle_time_ms).collect();
         let w = self.weights_by_call_count();
@@ -418,6 +745,47 @@ impl Log {
         Mean::compute(t.as_slice(), w.as_slice())
     }
 
+    fn weights_by_corrected_call_count(&self) -> Vec<f32> {
+        self.samples
+            .iter()
+            .map(|s| s.corrected_cycle_count as f32)
+            .collect()
+    }
+
+    fn corrected_cycle_time_ms(&self) -> Mean {
+        let t: Vec<f32> = self
+            .samples
+            .iter()
+            .map(|s| s.mean_corrected_cycle_time_ms)
+            .collect();
+        let w = self.weights_by_corrected_call_count();
+        Mean::compute(t.as_slice(), w.as_slice())
+    }
+
+    fn corrected_cycle_time_percentile(&self, p: Percentile) -> Mean {
+        let t: Vec<f32> = self
+            .samples
+            .iter()
+            .map(|s| s.corrected_cycle_time_percentiles[p as usize])
+            .collect();
+        let w = self.weights_by_corrected_call_count();
+        Mean::compute(t.as_slice(), w.as_slice())
+    }
+
+    /// Returns `None` if the concurrency limit was not adjusted adaptively
+    fn mean_concurrency_limit(&self) -> Option<Mean> {
+        let limits: Vec<f32> = self
+            .samples
+            .iter()
+            .map(|s| s.concurrency_limit.map(|l| l as f32))
+            .collect::<Option<_>>()?;
+        if limits.is_empty() {
+            return None;
+        }
+        let w = self.weights_by_call_count();
+        Some(Mean::compute(limits.as_slice(), w.as_slice()))
+    }
+
     fn mean_concurrency(&self) -> Mean {
         let p: Vec<f32> = self.samples.iter().map(|s| s.mean_queue_len).collect();
         let w = self.weights_by_request_count();
@@ -460,20 +828,118 @@ pub struct BenchmarkStats {
     pub cycle_count: u64,
     pub request_count: u64,
     pub requests_per_cycle: f64,
-    pub errors: Vec<String>,
     pub error_count: u64,
     pub errors_ratio: Option<f64>,
+    /// Failed requests by the category of the error
+    #[serde(default)]
+    pub error_stats: ErrorStats,
+    #[serde(default)]
+    pub retry_count: u64,
+    #[serde(default)]
+    pub retries_per_req: Option<f64>,
+    /// Maps the number of attempts to the number of requests that needed that many attempts
+    #[serde(default)]
+    pub attempts: BTreeMap<u64, u64>,
+    #[serde(default)]
+    pub speculative_count: u64,
+    /// Percentage of requests that triggered speculative execution
+    #[serde(default)]
+    pub speculative_ratio: Option<f64>,
+    /// Number of conditional requests (lightweight transactions) that were applied
+    #[serde(default)]
+    pub lwt_applied_count: u64,
+    /// Number of conditional requests that were not applied because the condition was not met
+    #[serde(default)]
+    pub lwt_rejected_count: u64,
+    /// Percentage of conditional requests that were not applied
+    #[serde(default)]
+    pub lwt_rejected_ratio: Option<f64>,
     pub row_count: u64,
     pub row_count_per_req: Option<f64>,
+    #[serde(default)]
+    pub batch_count: u64,
+    #[serde(default)]
+    pub statements_per_batch: Option<f64>,
+    /// Number of cycles started noticeably later than scheduled
+    #[serde(default)]
+    pub late_launch_count: u64,
+    /// Number of scheduled cycles skipped in the open-loop mode
+    #[serde(default)]
+    pub dropped_launch_count: u64,
     pub cycle_throughput: Mean,
     pub cycle_throughput_ratio: Option<f64>,
     pub req_throughput: Mean,
     pub row_throughput: Mean,
     pub cycle_time_ms: TimeDistribution,
+    /// Cycle times measured from the scheduled start times of the cycles.
+    /// Present only if the cycles were started on a schedule, i.e. the rate was limited.
+    #[serde(default)]
+    pub corrected_cycle_time_ms: Option<TimeDistribution>,
     pub resp_time_ms: Option<TimeDistribution>,
     pub concurrency: Mean,
     pub concurrency_ratio: f64,
+    /// Mean limit of concurrent requests per thread chosen by the adaptive concurrency controller
+    #[serde(default)]
+    pub concurrency_limit: Option<Mean>,
     pub log: Vec<Sample>,
+    /// Statistics of requests broken down by the statement
+    #[serde(default)]
+    pub statements: BTreeMap<String, StatementBenchmarkStats>,
+    /// Statistics of cycles broken down by the workload function
+    #[serde(default)]
+    pub functions: BTreeMap<String, FunctionBenchmarkStats>,
+    /// Slowest requests executed with tracing enabled; their traces are saved in the report
+    #[serde(skip)]
+    pub traced_requests: Vec<TracedRequest>,
+}
+
+/// Stores the final statistics of requests executed by a single statement
+#[derive(Serialize, Deserialize)]
+pub struct StatementBenchmarkStats {
+    pub request_count: u64,
+    pub error_count: u64,
+    pub errors_ratio: Option<f64>,
+    pub row_count: u64,
+    pub row_count_per_req: Option<f64>,
+    pub req_throughput: Mean,
+    pub resp_time_ms: TimeDistribution,
+    /// Counts of failed requests by the category of the error
+    #[serde(default)]
+    pub errors: BTreeMap<ErrorCategory, u64>,
+}
+
+/// Stores the final statistics of the calls of a single workload function
+#[derive(Serialize, Deserialize)]
+pub struct FunctionBenchmarkStats {
+    pub cycle_count: u64,
+    pub cycle_throughput: Mean,
+    pub cycle_time_ms: TimeDistribution,
+}
+
+/// Final statistics of a part of the workload, e.g. of a single statement or function
+pub trait BreakdownStats {
+    fn throughput(&self) -> Mean;
+    fn time_ms(&self) -> &TimeDistribution;
+}
+
+impl BreakdownStats for StatementBenchmarkStats {
+    fn throughput(&self) -> Mean {
+        self.req_throughput
+    }
+
+    fn time_ms(&self) -> &TimeDistribution {
+        &self.resp_time_ms
+    }
+}
+
+impl BreakdownStats for FunctionBenchmarkStats {
+    fn throughput(&self) -> Mean {
+        self.cycle_throughput
+    }
+
+    fn time_ms(&self) -> &TimeDistribution {
+        &self.cycle_time_ms
+    }
 }
 
 /// Stores the statistics of one or two test runs.
@@ -532,6 +998,43 @@ impl BenchmarkCmp<'_> {
     pub fn cmp_resp_time_percentile(&self, p: Percentile) -> Option<Significance> {
         self.cmp(|s| s.resp_time_ms.as_ref().map(|r| r.percentiles[p as usize]))
     }
+
+    /// Checks if corrected cycle time percentile is significantly different.
+    /// Returns None if the second benchmark is unset or the corrected times are missing in any run.
+    pub fn cmp_corrected_cycle_time_percentile(&self, p: Percentile) -> Option<Significance> {
+        self.cmp(|s| {
+            s.corrected_cycle_time_ms
+                .as_ref()
+                .map(|t| t.percentiles[p as usize])
+        })
+    }
+
+    /// Checks if throughput of a part of the workload, e.g. of a statement or a function,
+    /// is significantly different.
+    /// Returns None if the second benchmark is unset or the part is missing in any run.
+    pub fn cmp_breakdown_throughput<P: BreakdownStats>(
+        &self,
+        part: impl Fn(&BenchmarkStats) -> Option<&P>,
+    ) -> Option<Significance> {
+        self.cmp(|s| part(s).map(|p| p.throughput()))
+    }
+
+    /// Checks if mean time of a part of the workload is significantly different.
+    pub fn cmp_breakdown_mean_time<P: BreakdownStats>(
+        &self,
+        part: impl Fn(&BenchmarkStats) -> Option<&P>,
+    ) -> Option<Significance> {
+        self.cmp(|s| part(s).map(|p| p.time_ms().mean))
+    }
+
+    /// Checks if time percentile of a part of the workload is significantly different.
+    pub fn cmp_breakdown_time_percentile<P: BreakdownStats>(
+        &self,
+        part: impl Fn(&BenchmarkStats) -> Option<&P>,
+        p: Percentile,
+    ) -> Option<Significance> {
+        self.cmp(|s| part(s).map(|b| b.time_ms().percentiles[p as usize]))
+    }
 }
 
 /// Observes requests and computes their statistics such as mean throughput, mean response time,
@@ -546,12 +1049,25 @@ pub struct Recorder {
     pub start_cpu_time: ProcessTime,
     pub end_cpu_time: ProcessTime,
     pub cycle_count: u64,
+    pub late_launch_count: u64,
+    pub dropped_launch_count: u64,
     pub request_count: u64,
-    pub errors: HashSet<String>,
+    pub error_stats: ErrorStats,
     pub error_count: u64,
+    pub retry_count: u64,
+    pub speculative_count: u64,
+    pub lwt_applied_count: u64,
+    pub lwt_rejected_count: u64,
     pub row_count: u64,
+    pub batch_count: u64,
+    pub batch_statement_count: u64,
     pub cycle_times_ns: Histogram<u64>,
+    pub corrected_cycle_times_ns: Histogram<u64>,
     pub resp_times_ns: Histogram<u64>,
+    pub attempts: Histogram<u64>,
+    pub traced_requests: Vec<TracedRequest>,
+    pub statements: BTreeMap<String, StatementStats>,
+    pub functions: BTreeMap<String, FnStats>,
     pub queue_len_sum: u64,
     log: Log,
     rate_limit: Option<f64>,
@@ -562,6 +1078,8 @@ impl Recorder {
     /// Creates a new recorder.
     /// The `rate_limit` and `concurrency_limit` parameters are used only as the
     /// reference levels for relative throughput and relative parallelism.
+    /// If the concurrency limit is adjusted adaptively, the relative parallelism
+    /// refers to the mean adjusted limit instead.
     pub fn start(rate_limit: Option<f64>, concurrency_limit: NonZeroUsize) -> Recorder {
         let start_time = SystemTime::now();
         let start_instant = Instant::now();
@@ -576,19 +1094,38 @@ impl Recorder {
             rate_limit,
             concurrency_limit,
             cycle_count: 0,
+            late_launch_count: 0,
+            dropped_launch_count: 0,
             request_count: 0,
             row_count: 0,
-            errors: HashSet::new(),
+            batch_count: 0,
+            batch_statement_count: 0,
+            error_stats: ErrorStats::default(),
             error_count: 0,
+            retry_count: 0,
+            speculative_count: 0,
+            lwt_applied_count: 0,
+            lwt_rejected_count: 0,
             cycle_times_ns: Histogram::new(3).unwrap(),
+            corrected_cycle_times_ns: Histogram::new(3).unwrap(),
             resp_times_ns: Histogram::new(3).unwrap(),
+            attempts: Histogram::new(3).unwrap(),
+            traced_requests: Vec::new(),
+            statements: BTreeMap::new(),
+            functions: BTreeMap::new(),
             queue_len_sum: 0,
         }
     }
 
     /// Adds the statistics of the completed request to the already collected statistics.
     /// Called on completion of each sample.
-    pub fn record(&mut self, samples: &[WorkloadStats]) -> &Sample {
+    /// The `concurrency_limit` is the limit of concurrent requests per thread in effect
+    /// during the sample, if it was adjusted adaptively.
+    pub fn record(
+        &mut self,
+        samples: &[WorkloadStats],
+        concurrency_limit: Option<usize>,
+    ) -> &Sample {
         for s in samples.iter() {
             self.resp_times_ns
                 .add(&s.session_stats.resp_times_ns)
@@ -596,15 +1133,46 @@ impl Recorder {
             self.cycle_times_ns
                 .add(&s.function_stats.call_times_ns)
                 .unwrap();
+            self.corrected_cycle_times_ns
+                .add(&s.function_stats.corrected_call_times_ns)
+                .unwrap();
+            self.attempts.add(&s.session_stats.attempts).unwrap();
+            self.traced_requests
+                .extend(s.session_stats.traces.iter().cloned());
+            for (key, st) in s.session_stats.statements.iter() {
+                let acc = self.statements.entry(key.clone()).or_default();
+                acc.req_count += st.req_count;
+                acc.req_error_count += st.req_error_count;
+                acc.row_count += st.row_count;
+                acc.resp_times_ns.add(&st.resp_times_ns).unwrap();
+                for (category, count) in st.errors.iter() {
+                    *acc.errors.entry(*category).or_default() += count;
+                }
+            }
+            for (name, fs) in s.functions.iter() {
+                let acc = self.functions.entry(name.clone()).or_default();
+                acc.call_count += fs.call_count;
+                acc.call_times_ns.add(&fs.call_times_ns).unwrap();
+            }
         }
-        let stats = Sample::new(self.start_instant, samples);
+        if self.traced_requests.len() > MAX_KEPT_TRACES {
+            trace::keep_slowest(&mut self.traced_requests, MAX_KEPT_TRACES);
+        }
+        let mut stats = Sample::new(self.start_instant, samples);
+        stats.concurrency_limit = concurrency_limit;
         self.cycle_count += stats.cycle_count;
+        self.late_launch_count += stats.late_launch_count;
+        self.dropped_launch_count += stats.dropped_launch_count;
         self.request_count += stats.request_count;
         self.row_count += stats.row_count;
-        if self.errors.len() < MAX_KEPT_ERRORS {
-            self.errors.extend(stats.errors.iter().cloned());
-        }
+        self.batch_count += stats.batch_count;
+        self.batch_statement_count += stats.batch_statement_count;
+        self.error_stats.add(&stats.error_stats);
         self.error_count += stats.error_count;
+        self.retry_count += stats.retry_count;
+        self.speculative_count += stats.speculative_count;
+        self.lwt_applied_count += stats.lwt_applied_count;
+        self.lwt_rejected_count += stats.lwt_rejected_count;
         self.log.append(stats)
     }
 
@@ -632,7 +1200,9 @@ impl Recorder {
         let req_throughput = self.log.req_throughput();
         let row_throughput = self.log.row_throughput();
         let concurrency = self.log.mean_concurrency();
-        let concurrency_ratio = 100.0 * concurrency.value / self.concurrency_limit.get() as f64;
+        let concurrency_limit = self.log.mean_concurrency_limit();
+        let limit = concurrency_limit.map_or(self.concurrency_limit.get() as f64, |l| l.value);
+        let concurrency_ratio = 100.0 * concurrency.value / limit;
 
         let cycle_time_percentiles: Vec<Mean> = Percentile::iter()
             .map(|p| self.log.cycle_time_percentile(p))
@@ -640,6 +1210,41 @@ impl Recorder {
         let resp_time_percentiles: Vec<Mean> = Percentile::iter()
             .map(|p| self.log.resp_time_percentile(p))
             .collect();
+        let statements = self
+            .statements
+            .iter()
+            .map(|(key, st)| {
+                let (req_throughput, resp_time_ms) = self
+                    .log
+                    .breakdown_stats(|s| s.statements.get(key), &st.resp_times_ns);
+                let stats = StatementBenchmarkStats {
+                    request_count: st.req_count,
+                    error_count: st.req_error_count,
+                    errors_ratio: not_nan(100.0 * st.req_error_count as f64 / st.req_count as f64),
+                    row_count: st.row_count,
+                    row_count_per_req: not_nan(st.row_count as f64 / st.req_count as f64),
+                    req_throughput,
+                    resp_time_ms,
+                    errors: st.errors.clone(),
+                };
+                (key.clone(), stats)
+            })
+            .collect();
+        let functions = self
+            .functions
+            .iter()
+            .map(|(name, fs)| {
+                let (cycle_throughput, cycle_time_ms) = self
+                    .log
+                    .breakdown_stats(|s| s.functions.get(name), &fs.call_times_ns);
+                let stats = FunctionBenchmarkStats {
+                    cycle_count: fs.call_count,
+                    cycle_throughput,
+                    cycle_time_ms,
+                };
+                (name.clone(), stats)
+            })
+            .collect();
 
         BenchmarkStats {
             start_time: self.start_time.into(),
@@ -648,13 +1253,36 @@ impl Recorder {
             cpu_time_s,
             cpu_util,
             cycle_count: self.cycle_count,
-            errors: self.errors.into_iter().collect(),
             error_count: self.error_count,
             errors_ratio: not_nan(100.0 * self.error_count as f64 / count as f64),
+            error_stats: self.error_stats,
+            retry_count: self.retry_count,
+            retries_per_req: not_nan(self.retry_count as f64 / self.request_count as f64),
+            attempts: self
+                .attempts
+                .iter_recorded()
+                .map(|v| (v.value_iterated_to(), v.count_at_value()))
+                .collect(),
+            speculative_count: self.speculative_count,
+            speculative_ratio: not_nan(
+                100.0 * self.speculative_count as f64 / self.request_count as f64,
+            ),
+            lwt_applied_count: self.lwt_applied_count,
+            lwt_rejected_count: self.lwt_rejected_count,
+            lwt_rejected_ratio: not_nan(
+                100.0 * self.lwt_rejected_count as f64
+                    / (self.lwt_applied_count + self.lwt_rejected_count) as f64,
+            ),
             request_count: self.request_count,
             requests_per_cycle: self.request_count as f64 / self.cycle_count as f64,
             row_count: self.row_count,
             row_count_per_req: not_nan(self.row_count as f64 / self.request_count as f64),
+            batch_count: self.batch_count,
+            statements_per_batch: not_nan(
+                self.batch_statement_count as f64 / self.batch_count as f64,
+            ),
+            late_launch_count: self.late_launch_count,
+            dropped_launch_count: self.dropped_launch_count,
             cycle_throughput,
             cycle_throughput_ratio,
             req_throughput,
@@ -664,6 +1292,17 @@ impl Recorder {
                 percentiles: cycle_time_percentiles,
                 distribution: distribution(&self.cycle_times_ns),
             },
+            corrected_cycle_time_ms: if !self.corrected_cycle_times_ns.is_empty() {
+                Some(TimeDistribution {
+                    mean: self.log.corrected_cycle_time_ms(),
+                    percentiles: Percentile::iter()
+                        .map(|p| self.log.corrected_cycle_time_percentile(p))
+                        .collect(),
+                    distribution: distribution(&self.corrected_cycle_times_ns),
+                })
+            } else {
+                None
+            },
             resp_time_ms: if self.request_count > 0 {
                 Some(TimeDistribution {
                     mean: self.log.resp_time_ms(),
@@ -675,7 +1314,11 @@ impl Recorder {
             },
             concurrency,
             concurrency_ratio,
+            concurrency_limit,
             log: self.log.samples,
+            statements,
+            functions,
+            traced_requests: self.traced_requests,
         }
     }
 }
@@ -685,10 +1328,29 @@ mod test {
     use rand::distributions::Distribution;
     use rand::prelude::StdRng;
     use rand::SeedableRng;
+    use scylla::transport::errors::QueryError;
     use statrs::distribution::Normal;
     use statrs::statistics::Statistics;
-
-    use crate::stats::{t_test, Mean};
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+    use std::time::{Duration, Instant};
+
+    use crate::config::ErrorConf;
+    use crate::context::SessionStats;
+    use crate::stats::{t_test, ErrorCategory, ErrorStats, Mean, Recorder};
+    use crate::workload::{FnStats, WorkloadStats};
+
+    /// Returns the statistics of a one-second sample of the workload.
+    fn workload_stats(function_stats: FnStats, session_stats: SessionStats) -> WorkloadStats {
+        let now = Instant::now();
+        WorkloadStats {
+            start_time: now,
+            end_time: now + Duration::from_secs(1),
+            function_stats,
+            functions: BTreeMap::new(),
+            session_stats,
+        }
+    }
 
     /// Returns a random sample of size `len`.
     /// All data points i.i.d with N(`mean`, `std_dev`).
@@ -787,4 +1449,86 @@ mod test {
         assert!(t_test(&mean1, &mean2) < 0.0011);
         assert!(t_test(&mean2, &mean1) < 0.0011);
     }
+
+    #[test]
+    fn error_stats_keep_few_distinct_examples() {
+        let mut stats1 = ErrorStats::default();
+        for i in 0..10 {
+            stats1.record(ErrorCategory::Timeout, || format!("timeout {}", i % 2));
+        }
+        let mut stats2 = ErrorStats::default();
+        for i in 0..10 {
+            stats2.record(ErrorCategory::Timeout, || format!("timeout {i}"));
+        }
+        stats2.record(ErrorCategory::InvalidRequest, || "syntax".to_string());
+        stats1.add(&stats2);
+
+        assert_eq!(stats1.counts[&ErrorCategory::Timeout], 20);
+        assert_eq!(stats1.counts[&ErrorCategory::InvalidRequest], 1);
+        assert_eq!(
+            stats1.examples[&ErrorCategory::Timeout],
+            vec!["timeout 0", "timeout 1", "timeout 2"]
+        );
+    }
+
+    #[test]
+    fn request_count_includes_failed_requests() {
+        let mut session_stats = SessionStats::new();
+        for _ in 0..10 {
+            session_stats.start_request();
+            let result = Err(QueryError::TimeoutError);
+            session_stats.complete_request("s", Duration::from_millis(1), 1, &result);
+        }
+        let sample = workload_stats(FnStats::default(), session_stats);
+        let mut recorder = Recorder::start(None, NonZeroUsize::new(1).unwrap());
+        recorder.record(&[sample], None);
+        assert_eq!(recorder.request_count, 10);
+        assert_eq!(recorder.error_count, 10);
+
+        let errors = ErrorConf {
+            max_error_ratio: Some(60.0),
+            ..Default::default()
+        };
+        let exceeded = errors
+            .budget_exceeded(recorder.error_count, recorder.request_count)
+            .unwrap();
+        assert!(exceeded.starts_with("100.00%"), "{exceeded}");
+    }
+
+    #[test]
+    fn corrected_cycle_times_include_launch_delay() {
+        let mut recorder = Recorder::start(None, NonZeroUsize::new(1).unwrap());
+        for _ in 0..3 {
+            let mut fn_stats = FnStats::default();
+            for i in 0..100 {
+                let service_time = Duration::from_millis(1 + i % 10);
+                let launch_delay = Duration::from_millis(50);
+                fn_stats.operation_completed(service_time);
+                fn_stats.scheduled_operation_completed(service_time + launch_delay);
+            }
+            recorder.record(&[workload_stats(fn_stats, SessionStats::new())], None);
+        }
+        let stats = recorder.finish();
+        let corrected = stats.corrected_cycle_time_ms.unwrap();
+        assert!(corrected.mean.value >= stats.cycle_time_ms.mean.value);
+        for (c, s) in corrected
+            .percentiles
+            .iter()
+            .zip(stats.cycle_time_ms.percentiles.iter())
+        {
+            assert!(c.value >= s.value, "{} < {}", c.value, s.value);
+        }
+    }
+
+    #[test]
+    fn no_corrected_cycle_times_without_schedule() {
+        let mut recorder = Recorder::start(None, NonZeroUsize::new(1).unwrap());
+        let mut fn_stats = FnStats::default();
+        for i in 0..100 {
+            fn_stats.operation_completed(Duration::from_millis(1 + i % 10));
+        }
+        recorder.record(&[workload_stats(fn_stats, SessionStats::new())], None);
+        let stats = recorder.finish();
+        assert!(stats.corrected_cycle_time_ms.is_none());
+    }
 }
@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{Local, TimeZone};
+use console::style;
+use scylla::tracing::TracingInfo;
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+use uuid::Uuid;
+
+/// A request executed with CQL tracing enabled.
+/// The trace itself is stored by the server in `system_traces` and fetched after the run.
+#[derive(Clone, Debug)]
+pub struct TracedRequest {
+    pub tracing_id: Uuid,
+    pub cql: String,
+    pub duration: Duration,
+}
+
+/// Keeps only the `max_count` slowest traced requests.
+pub fn keep_slowest(traces: &mut Vec<TracedRequest>, max_count: usize) {
+    traces.sort_by_key(|t| std::cmp::Reverse(t.duration));
+    traces.truncate(max_count);
+}
+
+/// Server-side trace of a single request, saved in the report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestTrace {
+    pub tracing_id: String,
+    pub cql: String,
+    /// Response time measured by the client
+    pub resp_time_ms: f64,
+    /// Request duration reported by the coordinator
+    pub duration_us: Option<i32>,
+    pub coordinator: Option<String>,
+    /// Milliseconds since Unix epoch
+    pub started_at: Option<i64>,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub source: Option<String>,
+    /// Microseconds elapsed since the start of the request on the source node
+    pub source_elapsed_us: Option<i32>,
+    pub thread: Option<String>,
+    pub activity: Option<String>,
+}
+
+impl RequestTrace {
+    pub fn new(request: &TracedRequest, info: TracingInfo) -> RequestTrace {
+        let mut events: Vec<TraceEvent> = info
+            .events
+            .into_iter()
+            .map(|e| TraceEvent {
+                source: e.source.map(|s| s.to_string()),
+                source_elapsed_us: e.source_elapsed,
+                thread: e.thread,
+                activity: e.activity,
+            })
+            .collect();
+        events.sort_by_key(|e| (e.source.clone(), e.source_elapsed_us));
+        RequestTrace {
+            tracing_id: request.tracing_id.to_string(),
+            cql: request.cql.clone(),
+            resp_time_ms: request.duration.as_secs_f64() * 1000.0,
+            duration_us: info.duration,
+            coordinator: info.coordinator.map(|c| c.to_string()),
+            started_at: info.started_at.map(|t| t.0),
+            events,
+        }
+    }
+}
+
+impl Display for RequestTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let started_at = self
+            .started_at
+            .and_then(|t| Local.timestamp_millis_opt(t).single())
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "{} {:.3} ms  {}  coordinator: {}  {}",
+            style("Trace").yellow().bold().for_stdout(),
+            self.resp_time_ms,
+            self.tracing_id,
+            self.coordinator.as_deref().unwrap_or("?"),
+            started_at
+        )?;
+        writeln!(f, "{}", style(&self.cql).dim().for_stdout())?;
+        for e in &self.events {
+            writeln!(
+                f,
+                "  {:>15}  {:>9} µs  {:<28}  {}",
+                e.source.as_deref().unwrap_or("?"),
+                e.source_elapsed_us
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                e.thread.as_deref().unwrap_or(""),
+                e.activity.as_deref().unwrap_or("")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
+use std::future::Future;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
@@ -11,8 +12,9 @@ use rune::termcolor::{ColorChoice, StandardStream};
 use rune::{Any, Diagnostics, Module, Source, Sources, ToValue, Unit, Value, Vm};
 use try_lock::TryLock;
 
+use crate::config::ErrorKind;
 use crate::error::LatteError;
-use crate::{context, CassError, CassErrorKind, Context, SessionStats};
+use crate::{context, CassError, Context, SessionStats};
 
 /// Wraps a reference to Session that can be converted to a Rune `Value`
 /// and passed as one of `Args` arguments to a function.
@@ -107,9 +109,55 @@ impl Program {
         context_module
             .async_inst_fn("prepare", Context::prepare)
             .unwrap();
+        context_module
+            .async_inst_fn("prepare_with_options", Context::prepare_with_options)
+            .unwrap();
         context_module
             .async_inst_fn("execute_prepared", Context::execute_prepared)
             .unwrap();
+        context_module
+            .async_inst_fn("execute_conditional", Context::execute_conditional)
+            .unwrap();
+        context_module
+            .async_inst_fn(
+                "execute_prepared_with_profile",
+                Context::execute_prepared_with_profile,
+            )
+            .unwrap();
+        context_module
+            .async_inst_fn("execute_with_result", Context::execute_with_result)
+            .unwrap();
+        context_module
+            .async_inst_fn(
+                "execute_prepared_with_result",
+                Context::execute_prepared_with_result,
+            )
+            .unwrap();
+        context_module.inst_fn("pager", Context::pager).unwrap();
+        context_module.inst_fn("batch", Context::batch).unwrap();
+        context_module
+            .async_inst_fn("execute_batch", Context::execute_batch)
+            .unwrap();
+
+        let mut batch_module = Module::default();
+        batch_module.ty::<context::Batch>().unwrap();
+        batch_module.inst_fn("add", context::Batch::add).unwrap();
+        batch_module.inst_fn("len", context::Batch::len).unwrap();
+
+        let mut pager_module = Module::default();
+        pager_module.ty::<context::Pager>().unwrap();
+        pager_module
+            .async_inst_fn("next", context::Pager::next)
+            .unwrap();
+        pager_module
+            .inst_fn("has_more_pages", context::Pager::has_more_pages)
+            .unwrap();
+        pager_module
+            .inst_fn("paging_state", context::Pager::paging_state)
+            .unwrap();
+        pager_module
+            .inst_fn("set_paging_state", context::Pager::set_paging_state)
+            .unwrap();
 
         let mut err_module = Module::default();
         err_module.ty::<CassError>().unwrap();
@@ -126,6 +174,32 @@ impl Program {
             )
             .unwrap();
 
+        let mut types_module = Module::default();
+        types_module.ty::<context::Float32>().unwrap();
+        types_module.ty::<context::Counter>().unwrap();
+        types_module.ty::<context::Map>().unwrap();
+        types_module.ty::<context::Set>().unwrap();
+        macro_rules! register_displayable {
+            ($($t:ty),*) => {
+                $(
+                    types_module.ty::<$t>().unwrap();
+                    types_module
+                        .inst_fn(rune::runtime::Protocol::STRING_DISPLAY, <$t>::display)
+                        .unwrap();
+                )*
+            };
+        }
+        register_displayable!(
+            context::Date,
+            context::Time,
+            context::Timestamp,
+            context::Duration,
+            context::Decimal,
+            context::Varint,
+            context::Inet,
+            context::Timeuuid
+        );
+
         let mut latte_module = Module::with_crate("latte");
         latte_module.function(&["blob"], context::blob).unwrap();
         latte_module.function(&["now_timestamp"], context::now_timestamp).unwrap();
@@ -141,6 +215,27 @@ impl Program {
             .function(&["uuid"], context::Uuid::new)
             .unwrap();
         latte_module.function(&["normal"], context::normal).unwrap();
+        latte_module
+            .function(&["timeuuid"], context::Timeuuid::new)
+            .unwrap();
+        latte_module.function(&["date"], context::date).unwrap();
+        latte_module.function(&["time"], context::time).unwrap();
+        latte_module
+            .function(&["timestamp"], context::timestamp)
+            .unwrap();
+        latte_module
+            .function(&["duration"], context::duration)
+            .unwrap();
+        latte_module
+            .function(&["decimal"], context::decimal)
+            .unwrap();
+        latte_module.function(&["varint"], context::varint).unwrap();
+        latte_module.function(&["inet"], context::inet).unwrap();
+        latte_module
+            .function(&["counter"], context::counter)
+            .unwrap();
+        latte_module.function(&["map"], context::map).unwrap();
+        latte_module.function(&["set"], context::set).unwrap();
         latte_module
             .macro_(&["param"], move |ctx, ts| context::param(ctx, &params, ts))
             .unwrap();
@@ -155,6 +250,10 @@ impl Program {
             .unwrap();
         latte_module.inst_fn("to_i8", context::int_to_i8).unwrap();
         latte_module.inst_fn("to_i8", context::float_to_i8).unwrap();
+        latte_module.inst_fn("to_f32", context::int_to_f32).unwrap();
+        latte_module
+            .inst_fn("to_f32", context::float_to_f32)
+            .unwrap();
         latte_module.inst_fn("clamp", context::clamp_float).unwrap();
         latte_module.inst_fn("clamp", context::clamp_int).unwrap();
 
@@ -177,8 +276,11 @@ impl Program {
 
         let mut context = rune::Context::with_default_modules().unwrap();
         context.install(&context_module).unwrap();
+        context.install(&pager_module).unwrap();
+        context.install(&batch_module).unwrap();
         context.install(&err_module).unwrap();
         context.install(&uuid_module).unwrap();
+        context.install(&types_module).unwrap();
         context.install(&latte_module).unwrap();
         context.install(&fs_module).unwrap();
 
@@ -352,6 +454,13 @@ impl Program {
 pub struct FnStats {
     pub call_count: u64,
     pub call_times_ns: Histogram<u64>,
+    /// Call times measured from the scheduled start of the calls, including the time the calls
+    /// waited for being started. Recorded only for calls started on a schedule.
+    pub corrected_call_times_ns: Histogram<u64>,
+    /// Number of calls started too late after their scheduled time
+    pub late_launch_count: u64,
+    /// Number of scheduled calls not started because too many calls were in progress
+    pub dropped_launch_count: u64,
 }
 
 impl FnStats {
@@ -361,6 +470,16 @@ impl FnStats {
             .record(duration.as_nanos().clamp(1, u64::MAX as u128) as u64)
             .unwrap();
     }
+
+    pub fn scheduled_operation_completed(&mut self, duration_since_scheduled: Duration) {
+        self.corrected_call_times_ns
+            .record(
+                duration_since_scheduled
+                    .as_nanos()
+                    .clamp(1, u64::MAX as u128) as u64,
+            )
+            .unwrap();
+    }
 }
 
 impl Default for FnStats {
@@ -368,6 +487,9 @@ impl Default for FnStats {
         FnStats {
             call_count: 0,
             call_times_ns: Histogram::new(3).unwrap(),
+            corrected_call_times_ns: Histogram::new(3).unwrap(),
+            late_launch_count: 0,
+            dropped_launch_count: 0,
         }
     }
 }
@@ -377,6 +499,8 @@ pub struct WorkloadStats {
     pub start_time: Instant,
     pub end_time: Instant,
     pub function_stats: FnStats,
+    /// Statistics of the calls broken down by the function name
+    pub functions: BTreeMap<String, FnStats>,
     pub session_stats: SessionStats,
 }
 
@@ -384,63 +508,158 @@ pub struct WorkloadStats {
 pub struct WorkloadState {
     start_time: Instant,
     fn_stats: FnStats,
+    /// Indexed the same as `Workload::functions`
+    fn_stats_by_function: Vec<FnStats>,
 }
 
-impl Default for WorkloadState {
-    fn default() -> Self {
+impl WorkloadState {
+    fn new(function_count: usize) -> Self {
         WorkloadState {
             start_time: Instant::now(),
             fn_stats: Default::default(),
+            fn_stats_by_function: vec![FnStats::default(); function_count],
         }
     }
+
+    fn reset_fn_stats(&mut self) {
+        self.fn_stats = FnStats::default();
+        self.fn_stats_by_function
+            .iter_mut()
+            .for_each(|s| *s = FnStats::default());
+    }
 }
 
 pub struct Workload {
     context: Context,
     program: Program,
-    function: FnRef,
+    /// Functions to call, each with the cumulative weight of itself and all preceding functions
+    functions: Vec<(FnRef, f64)>,
+    /// Kinds of errors that don't stop the workload
+    tolerated_errors: Vec<ErrorKind>,
     state: TryLock<WorkloadState>,
 }
 
 impl Workload {
-    pub fn new(context: Context, program: Program, function: FnRef) -> Workload {
+    /// Creates a workload invoking given functions.
+    /// Each cycle invokes one function chosen pseudo-randomly according to the function weights.
+    /// Cycles failing with errors of the `tolerated_errors` kinds are counted as completed.
+    pub fn new(
+        context: Context,
+        program: Program,
+        functions: &[(FnRef, f64)],
+        tolerated_errors: &[ErrorKind],
+    ) -> Workload {
+        assert!(!functions.is_empty());
+        let mut total_weight = 0.0;
+        let functions = functions
+            .iter()
+            .map(|(f, weight)| {
+                total_weight += weight;
+                (f.clone(), total_weight)
+            })
+            .collect::<Vec<_>>();
         Workload {
+            state: TryLock::new(WorkloadState::new(functions.len())),
             context,
             program,
-            function,
-            state: TryLock::new(WorkloadState::default()),
+            functions,
+            tolerated_errors: tolerated_errors.to_vec(),
         }
     }
 
+    /// Deterministically picks the function to call in given cycle.
+    /// Returns the index of the function.
+    fn choose_function(&self, cycle: u64) -> usize {
+        if self.functions.len() == 1 {
+            return 0;
+        }
+        // Salted, so the choice is not correlated with the `hash(i)` values used by the script:
+        let hash = context::hash2(cycle as i64, 0x5eed);
+        let total_weight = self.functions.last().unwrap().1;
+        let x = hash as f64 / i64::MAX as f64 * total_weight;
+        self.functions
+            .iter()
+            .position(|(_, cumulative_weight)| x < *cumulative_weight)
+            .unwrap_or(self.functions.len() - 1)
+    }
+
     pub fn clone(&self) -> Result<Self, LatteError> {
         Ok(Workload {
             context: self.context.clone()?,
             // make a deep copy to avoid congestion on Arc ref counts used heavily by Rune
             program: self.program.unshare(),
-            function: self.function.clone(),
-            state: TryLock::new(WorkloadState::default()),
+            functions: self.functions.clone(),
+            tolerated_errors: self.tolerated_errors.clone(),
+            state: TryLock::new(WorkloadState::new(self.functions.len())),
         })
     }
 
+    /// Returns the reference to the contained context.
+    /// Allows to e.g. access context stats.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+}
+
+/// Runs the cycles of a workload and collects their statistics.
+/// Allows the executor to drive workloads that don't need a database session, e.g. in tests.
+pub trait CycleExecutor {
+    /// Executes a single cycle.
+    /// Returns the cycle number and the end time of the cycle.
+    fn run(
+        &self,
+        cycle: u64,
+        scheduled_start_time: Option<Instant>,
+    ) -> impl Future<Output = Result<(u64, Instant), LatteError>>;
+
+    /// Records a scheduled cycle that was started too late.
+    fn launch_late(&self);
+
+    /// Records a scheduled cycle that was skipped.
+    fn launch_dropped(&self);
+
+    /// Sets the start time and resets the counters.
+    fn reset(&self, start_time: Instant);
+
+    /// Returns statistics of the cycles executed so far and resets the counters.
+    fn take_stats(&self, end_time: Instant) -> WorkloadStats;
+}
+
+impl CycleExecutor for Workload {
     /// Executes a single cycle of a workload.
     /// This should be idempotent –
     /// the generated action should be a function of the iteration number.
+    /// If the cycle was scheduled to start at a given time, the time since then is recorded
+    /// as the corrected cycle time, which is not subject to coordinated omission.
     /// Returns the cycle number and the end time of the query.
-    pub async fn run(&self, cycle: u64) -> Result<(u64, Instant), LatteError> {
+    async fn run(
+        &self,
+        cycle: u64,
+        scheduled_start_time: Option<Instant>,
+    ) -> Result<(u64, Instant), LatteError> {
+        let function_index = self.choose_function(cycle);
+        let function = &self.functions[function_index].0;
         let start_time = Instant::now();
         let context = SessionRef::new(&self.context);
         let result = self
             .program
-            .async_call(&self.function, (context, cycle as i64))
+            .async_call(function, (context, cycle as i64))
             .await
             .map(|_| ()); // erase Value, because Value is !Send
         let end_time = Instant::now();
         let mut state = self.state.try_lock().unwrap();
         state.fn_stats.operation_completed(end_time - start_time);
+        state.fn_stats_by_function[function_index].operation_completed(end_time - start_time);
+        if let Some(scheduled_start_time) = scheduled_start_time {
+            let duration = end_time.saturating_duration_since(scheduled_start_time);
+            state.fn_stats.scheduled_operation_completed(duration);
+        }
         match result {
             Ok(_) => Ok((cycle, end_time)),
-            Err(LatteError::Cassandra(CassError(CassErrorKind::Overloaded(_, _)))) => {
-                // don't stop on overload errors;
+            Err(LatteError::Cassandra(e))
+                if self.tolerated_errors.iter().any(|k| e.is_of_kind(*k)) =>
+            {
+                // don't stop on tolerated errors, e.g. overload errors and other transient errors;
                 // they are being counted by the context stats anyways
                 Ok((cycle, end_time))
             }
@@ -448,34 +667,46 @@ impl Workload {
         }
     }
 
-    /// Returns the reference to the contained context.
-    /// Allows to e.g. access context stats.
-    pub fn context(&self) -> &Context {
-        &self.context
+    /// Records a scheduled cycle that was started too late.
+    fn launch_late(&self) {
+        let mut state = self.state.try_lock().unwrap();
+        state.fn_stats.late_launch_count += 1;
+    }
+
+    /// Records a scheduled cycle that was skipped.
+    fn launch_dropped(&self) {
+        let mut state = self.state.try_lock().unwrap();
+        state.fn_stats.dropped_launch_count += 1;
     }
 
     /// Sets the workload start time and resets the counters.
     /// Needed for producing `WorkloadStats` with
     /// recorded start and end times of measurement.
-    pub fn reset(&self, start_time: Instant) {
+    fn reset(&self, start_time: Instant) {
         let mut state = self.state.try_lock().unwrap();
-        state.fn_stats = FnStats::default();
+        state.reset_fn_stats();
         state.start_time = start_time;
         self.context.reset_session_stats();
     }
 
     /// Returns statistics of the operations invoked by this workload so far.
     /// Resets the internal statistic counters.
-    pub fn take_stats(&self, end_time: Instant) -> WorkloadStats {
+    fn take_stats(&self, end_time: Instant) -> WorkloadStats {
         let mut state = self.state.try_lock().unwrap();
         let result = WorkloadStats {
             start_time: state.start_time,
             end_time,
             function_stats: state.fn_stats.clone(),
+            functions: self
+                .functions
+                .iter()
+                .zip(state.fn_stats_by_function.iter())
+                .map(|((f, _), stats)| (f.name.clone(), stats.clone()))
+                .collect(),
             session_stats: self.context().take_session_stats(),
         };
         state.start_time = end_time;
-        state.fn_stats = FnStats::default();
+        state.reset_fn_stats();
         result
     }
 }